@@ -0,0 +1,254 @@
+/// The names bound in Python's `builtins` module, together with the attributes every module has
+/// implicitly. Keywords like `True`, `False` and `None` are not included.
+pub const BUILTINS: &[&str] = &[
+    "__build_class__",
+    "__builtins__",
+    "__debug__",
+    "__doc__",
+    "__file__",
+    "__import__",
+    "__loader__",
+    "__name__",
+    "__package__",
+    "__spec__",
+    "ArithmeticError",
+    "AssertionError",
+    "AttributeError",
+    "BaseException",
+    "BaseExceptionGroup",
+    "BlockingIOError",
+    "BrokenPipeError",
+    "BufferError",
+    "BytesWarning",
+    "ChildProcessError",
+    "ConnectionAbortedError",
+    "ConnectionError",
+    "ConnectionRefusedError",
+    "ConnectionResetError",
+    "DeprecationWarning",
+    "EOFError",
+    "Ellipsis",
+    "EncodingWarning",
+    "EnvironmentError",
+    "Exception",
+    "ExceptionGroup",
+    "FileExistsError",
+    "FileNotFoundError",
+    "FloatingPointError",
+    "FutureWarning",
+    "GeneratorExit",
+    "IOError",
+    "ImportError",
+    "ImportWarning",
+    "IndentationError",
+    "IndexError",
+    "InterruptedError",
+    "IsADirectoryError",
+    "KeyError",
+    "KeyboardInterrupt",
+    "LookupError",
+    "MemoryError",
+    "ModuleNotFoundError",
+    "NameError",
+    "NotADirectoryError",
+    "NotImplemented",
+    "NotImplementedError",
+    "OSError",
+    "OverflowError",
+    "PendingDeprecationWarning",
+    "PermissionError",
+    "ProcessLookupError",
+    "PythonFinalizationError",
+    "RecursionError",
+    "ReferenceError",
+    "ResourceWarning",
+    "RuntimeError",
+    "RuntimeWarning",
+    "StopAsyncIteration",
+    "StopIteration",
+    "SyntaxError",
+    "SyntaxWarning",
+    "SystemError",
+    "SystemExit",
+    "TabError",
+    "TimeoutError",
+    "TypeError",
+    "UnboundLocalError",
+    "UnicodeDecodeError",
+    "UnicodeEncodeError",
+    "UnicodeError",
+    "UnicodeTranslateError",
+    "UnicodeWarning",
+    "UserWarning",
+    "ValueError",
+    "Warning",
+    "ZeroDivisionError",
+    "abs",
+    "aiter",
+    "all",
+    "anext",
+    "any",
+    "ascii",
+    "bin",
+    "bool",
+    "breakpoint",
+    "bytearray",
+    "bytes",
+    "callable",
+    "chr",
+    "classmethod",
+    "compile",
+    "complex",
+    "copyright",
+    "credits",
+    "delattr",
+    "dict",
+    "dir",
+    "divmod",
+    "enumerate",
+    "eval",
+    "exec",
+    "exit",
+    "filter",
+    "float",
+    "format",
+    "frozenset",
+    "getattr",
+    "globals",
+    "hasattr",
+    "hash",
+    "help",
+    "hex",
+    "id",
+    "input",
+    "int",
+    "isinstance",
+    "issubclass",
+    "iter",
+    "len",
+    "license",
+    "list",
+    "locals",
+    "map",
+    "max",
+    "memoryview",
+    "min",
+    "next",
+    "object",
+    "oct",
+    "open",
+    "ord",
+    "pow",
+    "print",
+    "property",
+    "quit",
+    "range",
+    "repr",
+    "reversed",
+    "round",
+    "set",
+    "setattr",
+    "slice",
+    "sorted",
+    "staticmethod",
+    "str",
+    "sum",
+    "super",
+    "tuple",
+    "type",
+    "vars",
+    "zip",
+];
+
+/// The builtins that are classes, apart from the exception and warning classes, which are exactly
+/// the builtins starting with an uppercase letter except for `Ellipsis` and `NotImplemented`.
+const LOWERCASE_CLASSES: &[&str] = &[
+    "bool",
+    "bytearray",
+    "bytes",
+    "classmethod",
+    "complex",
+    "dict",
+    "enumerate",
+    "filter",
+    "float",
+    "frozenset",
+    "int",
+    "list",
+    "map",
+    "memoryview",
+    "object",
+    "property",
+    "range",
+    "reversed",
+    "set",
+    "slice",
+    "staticmethod",
+    "str",
+    "super",
+    "tuple",
+    "type",
+    "zip",
+];
+
+/// Whether the builtin `name` is a class and can hence be used as a type.
+pub fn is_builtin_class(name: &str) -> bool {
+    match name.chars().next() {
+        Some(first) if first.is_ascii_uppercase() => {
+            BUILTINS.contains(&name) && !matches!(name, "Ellipsis" | "NotImplemented")
+        }
+        _ => LOWERCASE_CLASSES.contains(&name),
+    }
+}
+
+/// Returns the base classes of the builtin class `name` other than `object`.
+fn builtin_bases(name: &str) -> &'static [&'static str] {
+    match name {
+        "bool" => &["int"],
+        "BaseExceptionGroup" | "Exception" | "GeneratorExit" | "KeyboardInterrupt"
+        | "SystemExit" => &["BaseException"],
+        "ArithmeticError" | "AssertionError" | "AttributeError" | "BufferError" | "EOFError"
+        | "ImportError" | "LookupError" | "MemoryError" | "NameError" | "OSError"
+        | "ReferenceError" | "RuntimeError" | "StopAsyncIteration" | "StopIteration"
+        | "SyntaxError" | "SystemError" | "TypeError" | "ValueError" | "Warning" => &["Exception"],
+        "ExceptionGroup" => &["BaseExceptionGroup", "Exception"],
+        "FloatingPointError" | "OverflowError" | "ZeroDivisionError" => &["ArithmeticError"],
+        "ModuleNotFoundError" => &["ImportError"],
+        "IndexError" | "KeyError" => &["LookupError"],
+        "UnboundLocalError" => &["NameError"],
+        // `EnvironmentError` and `IOError` are aliases of `OSError`.
+        "BlockingIOError" | "ChildProcessError" | "ConnectionError" | "EnvironmentError"
+        | "FileExistsError" | "FileNotFoundError" | "IOError" | "InterruptedError"
+        | "IsADirectoryError" | "NotADirectoryError" | "PermissionError" | "ProcessLookupError"
+        | "TimeoutError" => &["OSError"],
+        "BrokenPipeError"
+        | "ConnectionAbortedError"
+        | "ConnectionRefusedError"
+        | "ConnectionResetError" => &["ConnectionError"],
+        "NotImplementedError" | "PythonFinalizationError" | "RecursionError" => &["RuntimeError"],
+        "IndentationError" => &["SyntaxError"],
+        "TabError" => &["IndentationError"],
+        "UnicodeError" => &["ValueError"],
+        "UnicodeDecodeError" | "UnicodeEncodeError" | "UnicodeTranslateError" => &["UnicodeError"],
+        "BytesWarning"
+        | "DeprecationWarning"
+        | "EncodingWarning"
+        | "FutureWarning"
+        | "ImportWarning"
+        | "PendingDeprecationWarning"
+        | "ResourceWarning"
+        | "RuntimeWarning"
+        | "SyntaxWarning"
+        | "UnicodeWarning"
+        | "UserWarning" => &["Warning"],
+        _ => &[],
+    }
+}
+
+/// Whether the builtin class `name` is `base` or a subclass of it.
+pub fn is_builtin_subclass(name: &str, base: &str) -> bool {
+    name == base
+        || builtin_bases(name)
+            .iter()
+            .any(|parent| is_builtin_subclass(parent, base))
+}
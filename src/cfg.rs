@@ -0,0 +1,584 @@
+//! Control-flow graphs for the code blocks of a module, i.e., the module itself and the bodies of
+//! its functions and classes.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use ast::HasNodeIndex;
+use text_size::{Ranged, TextRange};
+
+use crate::indexed::IndexedModule;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct BlockId(u32);
+
+impl BlockId {
+    fn from_index(index: usize) -> Self {
+        Self(index.try_into().expect("More than 4G blocks? Wow!"))
+    }
+
+    fn into_index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl std::fmt::Display for BlockId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EdgeKind {
+    /// An unconditional transfer of control.
+    Jump,
+    /// The condition at the end of the block holds, e.g., the test of an `if` statement, the
+    /// pattern of a `case` or a `for` loop has another item.
+    True,
+    /// The condition at the end of the block does not hold.
+    False,
+    /// An exception is raised in the block.
+    Exception,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Edge {
+    pub target: BlockId,
+    pub kind: EdgeKind,
+}
+
+/// A node executed in a basic block. This is either a simple statement or the part of a compound
+/// statement that is executed when the control reaches it, e.g., the test of an `if` statement or
+/// a `case` of a `match` statement. Function and class definitions are simple statements here,
+/// their bodies have their own graphs.
+#[derive(Clone, Copy, Debug)]
+pub struct Element {
+    pub node: ast::NodeIndex,
+    pub range: TextRange,
+}
+
+#[derive(Debug, Default)]
+pub struct BasicBlock {
+    pub elements: Vec<Element>,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug)]
+pub struct ControlFlowGraph {
+    blocks: Vec<BasicBlock>,
+}
+
+impl ControlFlowGraph {
+    pub const ENTRY: BlockId = BlockId(0);
+    /// The block all returns and uncaught exceptions lead to. It is always empty.
+    pub const EXIT: BlockId = BlockId(1);
+
+    fn new() -> Self {
+        Self {
+            blocks: Vec::from([BasicBlock::default(), BasicBlock::default()]),
+        }
+    }
+
+    pub fn get(&self, id: BlockId) -> &BasicBlock {
+        &self.blocks[id.into_index()]
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = (BlockId, &BasicBlock)> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| (BlockId::from_index(index), block))
+    }
+
+    /// Renders the graph for debugging and snapshot tests. Every element is rendered as the first
+    /// line of its source text.
+    pub fn dump(&self, source: &str) -> String {
+        let mut output = String::new();
+        for (id, block) in self.blocks() {
+            let label = match id {
+                Self::ENTRY => " (entry)",
+                Self::EXIT => " (exit)",
+                _ => "",
+            };
+            writeln!(output, "{id}{label}:").unwrap();
+            for element in &block.elements {
+                let text = source[element.range].lines().next().unwrap_or_default();
+                writeln!(output, "  {}", text.trim_end()).unwrap();
+            }
+            for edge in &block.edges {
+                writeln!(output, "  {:?} -> {}", edge.kind, edge.target).unwrap();
+            }
+        }
+        output
+    }
+
+    /// Removes the empty blocks that cannot be reached and renumbers the remaining ones.
+    fn compact(mut self) -> Self {
+        let mut removed = vec![false; self.blocks.len()];
+        loop {
+            let mut reached = vec![false; self.blocks.len()];
+            for (index, block) in self.blocks.iter().enumerate() {
+                if !removed[index] {
+                    for edge in &block.edges {
+                        reached[edge.target.into_index()] = true;
+                    }
+                }
+            }
+            let mut changed = false;
+            for (index, block) in self.blocks.iter().enumerate().skip(2) {
+                if !removed[index] && !reached[index] && block.elements.is_empty() {
+                    removed[index] = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        let mut renumbered = Vec::with_capacity(self.blocks.len());
+        let mut next = 0;
+        for is_removed in &removed {
+            renumbered.push(BlockId::from_index(next));
+            if !is_removed {
+                next += 1;
+            }
+        }
+        self.blocks = std::mem::take(&mut self.blocks)
+            .into_iter()
+            .zip(removed)
+            .filter(|(_, is_removed)| !is_removed)
+            .map(|(mut block, _)| {
+                for edge in &mut block.edges {
+                    edge.target = renumbered[edge.target.into_index()];
+                }
+                block
+            })
+            .collect();
+        self
+    }
+}
+
+/// The control-flow graphs of the module and all functions and classes in it, keyed by the node
+/// index of the module or the function or class definition.
+#[derive(Debug)]
+pub struct ControlFlowGraphs {
+    graphs: HashMap<ast::NodeIndex, ControlFlowGraph>,
+}
+
+impl ControlFlowGraphs {
+    pub fn new(module: &IndexedModule) -> Self {
+        let syntax = module.syntax();
+        let mut graphs = HashMap::new();
+        let mut queue = Vec::from([(syntax.node_index.load(), syntax.body.as_slice())]);
+        while let Some((node, body)) = queue.pop() {
+            let mut builder = GraphBuilder::new();
+            builder.add_body(body);
+            builder.jump(ControlFlowGraph::EXIT);
+            queue.append(&mut builder.nested);
+            graphs.insert(node, builder.graph.compact());
+        }
+        Self { graphs }
+    }
+
+    pub fn get(&self, node: ast::NodeIndex) -> Option<&ControlFlowGraph> {
+        self.graphs.get(&node)
+    }
+
+    /// Renders all graphs in source order for debugging and snapshot tests.
+    pub fn dump(&self, source: &str) -> String {
+        let mut nodes: Vec<_> = self.graphs.keys().copied().collect();
+        nodes.sort_by_key(|node| node.as_usize());
+        let mut output = String::new();
+        for node in nodes {
+            writeln!(output, "graph #{}:", node.as_usize()).unwrap();
+            output.push_str(&self.graphs[&node].dump(source));
+        }
+        output
+    }
+}
+
+/// The ways to leave a block of statements other than falling through.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Exit {
+    Return,
+    Raise,
+    Break,
+    Continue,
+}
+
+/// The compound statements around the statement we are currently adding that can catch an
+/// [`Exit`].
+enum Frame {
+    Loop {
+        header: BlockId,
+        after: BlockId,
+    },
+    Try {
+        dispatch: BlockId,
+    },
+    /// All exits pass through the `finally` clause. We remember them to continue them at the end
+    /// of the clause.
+    Finally {
+        entry: BlockId,
+        exits: Vec<Exit>,
+    },
+}
+
+struct GraphBuilder<'m> {
+    graph: ControlFlowGraph,
+    /// The block we are currently adding elements to. `None` if the current point is
+    /// unreachable, e.g., after a `return`.
+    current: Option<BlockId>,
+    frames: Vec<Frame>,
+    /// The bodies of the functions and classes defined in the graph.
+    nested: Vec<(ast::NodeIndex, &'m [ast::Stmt])>,
+}
+
+impl<'m> GraphBuilder<'m> {
+    fn new() -> Self {
+        Self {
+            graph: ControlFlowGraph::new(),
+            current: Some(ControlFlowGraph::ENTRY),
+            frames: Vec::new(),
+            nested: Vec::new(),
+        }
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        let id = BlockId::from_index(self.graph.blocks.len());
+        self.graph.blocks.push(BasicBlock::default());
+        id
+    }
+
+    fn add_edge(&mut self, from: BlockId, target: BlockId, kind: EdgeKind) {
+        let edge = Edge { target, kind };
+        let edges = &mut self.graph.blocks[from.into_index()].edges;
+        if !edges.contains(&edge) {
+            edges.push(edge);
+        }
+    }
+
+    /// Adds an edge from the current block, if the current point is reachable.
+    fn branch(&mut self, kind: EdgeKind, target: BlockId) {
+        if let Some(current) = self.current {
+            self.add_edge(current, target, kind);
+        }
+    }
+
+    fn jump(&mut self, target: BlockId) {
+        self.branch(EdgeKind::Jump, target);
+    }
+
+    /// Adds `node` to the current block. Unreachable code gets a block of its own.
+    fn push(&mut self, node: impl HasNodeIndex + Ranged) {
+        let block = match self.current {
+            Some(block) => block,
+            None => {
+                let block = self.new_block();
+                self.current = Some(block);
+                block
+            }
+        };
+        let element = Element {
+            node: node.node_index().load(),
+            range: node.range(),
+        };
+        let elements = &mut self.graph.blocks[block.into_index()].elements;
+        elements.push(element);
+        // Any element can raise an exception. We only add the edge once per block and only if
+        // the exception can be caught in the graph.
+        let catching = self
+            .frames
+            .iter()
+            .any(|frame| matches!(frame, Frame::Try { .. } | Frame::Finally { .. }));
+        if elements.len() == 1 && catching {
+            self.route(block, Exit::Raise, self.frames.len());
+        }
+    }
+
+    /// Adds the edge for leaving `from` via `exit` to the closest of the first `depth` frames that
+    /// catches it.
+    fn route(&mut self, from: BlockId, exit: Exit, depth: usize) {
+        let kind = if exit == Exit::Raise {
+            EdgeKind::Exception
+        } else {
+            EdgeKind::Jump
+        };
+        let target = self.frames[..depth]
+            .iter_mut()
+            .rev()
+            .find_map(|frame| match frame {
+                Frame::Loop { header, after } => match exit {
+                    Exit::Break => Some(*after),
+                    Exit::Continue => Some(*header),
+                    Exit::Return | Exit::Raise => None,
+                },
+                Frame::Try { dispatch } => (exit == Exit::Raise).then_some(*dispatch),
+                Frame::Finally { entry, exits } => {
+                    if !exits.contains(&exit) {
+                        exits.push(exit);
+                    }
+                    Some(*entry)
+                }
+            });
+        match target {
+            Some(target) => self.add_edge(from, target, kind),
+            None => {
+                // `break` and `continue` outside of a loop are syntax errors.
+                if matches!(exit, Exit::Return | Exit::Raise) {
+                    self.add_edge(from, ControlFlowGraph::EXIT, kind);
+                }
+            }
+        }
+    }
+
+    /// Leaves the current block via `exit`. The current point is unreachable afterwards.
+    fn exit(&mut self, exit: Exit) {
+        if let Some(current) = self.current {
+            self.route(current, exit, self.frames.len());
+        }
+        self.current = None;
+    }
+
+    fn add_body(&mut self, body: &'m [ast::Stmt]) {
+        for stmt in body {
+            self.add_stmt(stmt);
+        }
+    }
+
+    /// Adds `test` to the current block and `body` as the branch taken when `test` holds. Returns
+    /// the block to continue with when `test` does not hold.
+    fn add_conditional(
+        &mut self,
+        test: &'m ast::Expr,
+        body: &'m [ast::Stmt],
+        after: BlockId,
+    ) -> Option<BlockId> {
+        self.push(test);
+        let test_block = self.current;
+        let then = self.new_block();
+        self.branch(EdgeKind::True, then);
+        self.current = Some(then);
+        self.add_body(body);
+        self.jump(after);
+        test_block
+    }
+
+    fn add_stmt(&mut self, stmt: &'m ast::Stmt) {
+        match stmt {
+            ast::Stmt::FunctionDef(func_def) => {
+                self.push(stmt);
+                self.nested
+                    .push((func_def.node_index.load(), func_def.body.as_slice()));
+            }
+            ast::Stmt::ClassDef(class_def) => {
+                self.push(stmt);
+                self.nested
+                    .push((class_def.node_index.load(), class_def.body.as_slice()));
+            }
+            ast::Stmt::If(if_stmt) => {
+                let after = self.new_block();
+                let mut otherwise = self.add_conditional(&if_stmt.test, &if_stmt.body, after);
+                for clause in &if_stmt.elif_else_clauses {
+                    let block = self.new_block();
+                    self.current = otherwise;
+                    self.branch(EdgeKind::False, block);
+                    self.current = Some(block);
+                    otherwise = match &clause.test {
+                        Some(test) => self.add_conditional(test, &clause.body, after),
+                        None => {
+                            self.add_body(&clause.body);
+                            self.jump(after);
+                            None
+                        }
+                    };
+                }
+                self.current = otherwise;
+                self.branch(EdgeKind::False, after);
+                self.current = Some(after);
+            }
+            ast::Stmt::While(while_stmt) => {
+                let header = self.new_block();
+                let after = self.new_block();
+                self.jump(header);
+                self.current = Some(header);
+                self.push(&*while_stmt.test);
+                let body = self.new_block();
+                self.branch(EdgeKind::True, body);
+                let orelse = self.new_block();
+                // `while True:` can only be left with a `break`.
+                if !matches!(&*while_stmt.test, ast::Expr::BooleanLiteral(literal) if literal.value)
+                {
+                    self.branch(EdgeKind::False, orelse);
+                }
+                self.add_loop_body(header, after, body, &while_stmt.body);
+                self.current = Some(orelse);
+                self.add_body(&while_stmt.orelse);
+                self.jump(after);
+                self.current = Some(after);
+            }
+            ast::Stmt::For(for_stmt) => {
+                self.push(&*for_stmt.iter);
+                let header = self.new_block();
+                let after = self.new_block();
+                self.jump(header);
+                self.current = Some(header);
+                // The `for` statement stands for fetching the next item and binding the target.
+                self.push(stmt);
+                let body = self.new_block();
+                self.branch(EdgeKind::True, body);
+                let orelse = self.new_block();
+                self.branch(EdgeKind::False, orelse);
+                self.add_loop_body(header, after, body, &for_stmt.body);
+                self.current = Some(orelse);
+                self.add_body(&for_stmt.orelse);
+                self.jump(after);
+                self.current = Some(after);
+            }
+            ast::Stmt::Try(try_stmt) => self.add_try(try_stmt),
+            ast::Stmt::With(with) => {
+                // The `with` statement stands for evaluating the context managers and entering
+                // them. We do not model context managers suppressing exceptions.
+                self.push(stmt);
+                self.add_body(&with.body);
+            }
+            ast::Stmt::Match(match_stmt) => {
+                self.push(&*match_stmt.subject);
+                let after = self.new_block();
+                for case in &match_stmt.cases {
+                    self.push(case);
+                    let body = self.new_block();
+                    let irrefutable = case.guard.is_none()
+                        && matches!(
+                            case.pattern,
+                            ast::Pattern::MatchAs(ast::PatternMatchAs { pattern: None, .. })
+                        );
+                    let next = if irrefutable {
+                        self.jump(body);
+                        None
+                    } else {
+                        self.branch(EdgeKind::True, body);
+                        let next = self.new_block();
+                        self.branch(EdgeKind::False, next);
+                        Some(next)
+                    };
+                    self.current = Some(body);
+                    self.add_body(&case.body);
+                    self.jump(after);
+                    self.current = next;
+                }
+                self.jump(after);
+                self.current = Some(after);
+            }
+            ast::Stmt::Return(_) => {
+                self.push(stmt);
+                self.exit(Exit::Return);
+            }
+            ast::Stmt::Raise(_) => {
+                self.push(stmt);
+                self.exit(Exit::Raise);
+            }
+            ast::Stmt::Break(_) => {
+                self.push(stmt);
+                self.exit(Exit::Break);
+            }
+            ast::Stmt::Continue(_) => {
+                self.push(stmt);
+                self.exit(Exit::Continue);
+            }
+            _ => self.push(stmt),
+        }
+    }
+
+    fn add_loop_body(
+        &mut self,
+        header: BlockId,
+        after: BlockId,
+        body: BlockId,
+        stmts: &'m [ast::Stmt],
+    ) {
+        self.frames.push(Frame::Loop { header, after });
+        self.current = Some(body);
+        self.add_body(stmts);
+        self.jump(header);
+        self.frames.pop();
+    }
+
+    fn add_try(&mut self, try_stmt: &'m ast::StmtTry) {
+        let after = self.new_block();
+        let finally = (!try_stmt.finalbody.is_empty()).then(|| self.new_block());
+        if let Some(entry) = finally {
+            let exits = Vec::new();
+            self.frames.push(Frame::Finally { entry, exits });
+        }
+        let dispatch = (!try_stmt.handlers.is_empty()).then(|| self.new_block());
+        if let Some(dispatch) = dispatch {
+            self.frames.push(Frame::Try { dispatch });
+        }
+        // The body needs a block of its own since its elements can raise exceptions.
+        let body = self.new_block();
+        self.jump(body);
+        self.current = Some(body);
+        self.add_body(&try_stmt.body);
+        if dispatch.is_some() {
+            self.frames.pop();
+        }
+        // Exceptions in the `else` clause are not handled by the handlers.
+        if !try_stmt.orelse.is_empty() {
+            let orelse = self.new_block();
+            self.jump(orelse);
+            self.current = Some(orelse);
+            self.add_body(&try_stmt.orelse);
+        }
+        let done = finally.unwrap_or(after);
+        // Whether the statement can complete normally, i.e., without an exit passing through it.
+        let mut completed = self.current.is_some();
+        self.jump(done);
+
+        if let Some(dispatch) = dispatch {
+            self.current = Some(dispatch);
+            for handler in &try_stmt.handlers {
+                let ast::ExceptHandler::ExceptHandler(except) = handler;
+                self.push(handler);
+                let body = self.new_block();
+                let next = if except.type_.is_some() {
+                    self.branch(EdgeKind::True, body);
+                    let next = self.new_block();
+                    self.branch(EdgeKind::False, next);
+                    Some(next)
+                } else {
+                    // A bare `except:` catches everything.
+                    self.jump(body);
+                    None
+                };
+                self.current = Some(body);
+                self.add_body(&except.body);
+                completed |= self.current.is_some();
+                self.jump(done);
+                self.current = next;
+            }
+            // No handler matches, the exception propagates.
+            self.exit(Exit::Raise);
+        }
+
+        if let Some(entry) = finally {
+            let Some(Frame::Finally { exits, .. }) = self.frames.pop() else {
+                unreachable!("The `finally` frame is the innermost one.");
+            };
+            self.current = Some(entry);
+            self.add_body(&try_stmt.finalbody);
+            if let Some(end) = self.current {
+                for exit in exits {
+                    self.route(end, exit, self.frames.len());
+                }
+            }
+            if completed {
+                self.jump(after);
+            }
+        }
+        self.current = Some(after);
+    }
+}
@@ -0,0 +1,298 @@
+use super::*;
+
+/// Renders the graphs of all code blocks in `source`.
+fn dump(source: &str) -> String {
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    ControlFlowGraphs::new(&module).dump(source)
+}
+
+#[test]
+fn if_elif_else() {
+    let source = "\
+x = 0
+if a:
+    x = 1
+elif b:
+    x = 2
+else:
+    x = 3
+print(x)
+";
+    assert_eq!(
+        dump(source),
+        "\
+graph #0:
+bb0 (entry):
+  x = 0
+  a
+  True -> bb3
+  False -> bb4
+bb1 (exit):
+bb2:
+  print(x)
+  Jump -> bb1
+bb3:
+  x = 1
+  Jump -> bb2
+bb4:
+  b
+  True -> bb5
+  False -> bb6
+bb5:
+  x = 2
+  Jump -> bb2
+bb6:
+  x = 3
+  Jump -> bb2
+"
+    );
+}
+
+#[test]
+fn loops_with_else_break_and_continue() {
+    let source = "\
+for x in xs:
+    if x:
+        break
+else:
+    y = 1
+while c:
+    continue
+";
+    assert_eq!(
+        dump(source),
+        "\
+graph #0:
+bb0 (entry):
+  xs
+  Jump -> bb2
+bb1 (exit):
+bb2:
+  for x in xs:
+  True -> bb4
+  False -> bb5
+bb3:
+  Jump -> bb8
+bb4:
+  x
+  True -> bb7
+  False -> bb6
+bb5:
+  y = 1
+  Jump -> bb3
+bb6:
+  Jump -> bb2
+bb7:
+  break
+  Jump -> bb3
+bb8:
+  c
+  True -> bb10
+  False -> bb11
+bb9:
+  Jump -> bb1
+bb10:
+  continue
+  Jump -> bb8
+bb11:
+  Jump -> bb9
+"
+    );
+}
+
+#[test]
+fn try_except_else_finally() {
+    let source = "\
+def f():
+    try:
+        x = g()
+    except ValueError:
+        return 1
+    except:
+        raise
+    else:
+        x += 1
+    finally:
+        cleanup()
+    return x
+";
+    assert_eq!(
+        dump(source),
+        "\
+graph #0:
+bb0 (entry):
+  def f():
+  Jump -> bb1
+bb1 (exit):
+graph #1:
+bb0 (entry):
+  Jump -> bb5
+bb1 (exit):
+bb2:
+  return x
+  Jump -> bb1
+bb3:
+  cleanup()
+  Exception -> bb1
+  Jump -> bb1
+  Jump -> bb2
+bb4:
+  except ValueError:
+  Exception -> bb3
+  True -> bb7
+  False -> bb8
+bb5:
+  x = g()
+  Exception -> bb4
+  Jump -> bb6
+bb6:
+  x += 1
+  Exception -> bb3
+  Jump -> bb3
+bb7:
+  return 1
+  Exception -> bb3
+  Jump -> bb3
+bb8:
+  except:
+  Exception -> bb3
+  Jump -> bb9
+bb9:
+  raise
+  Exception -> bb3
+"
+    );
+}
+
+#[test]
+fn try_finally_without_normal_completion() {
+    let source = "\
+def f():
+    try:
+        return g()
+    except ValueError:
+        raise
+    finally:
+        cleanup()
+    x = 1
+";
+    assert_eq!(
+        dump(source),
+        "\
+graph #0:
+bb0 (entry):
+  def f():
+  Jump -> bb1
+bb1 (exit):
+graph #1:
+bb0 (entry):
+  Jump -> bb5
+bb1 (exit):
+bb2:
+  x = 1
+  Jump -> bb1
+bb3:
+  cleanup()
+  Jump -> bb1
+  Exception -> bb1
+bb4:
+  except ValueError:
+  Exception -> bb3
+  True -> bb6
+  False -> bb7
+bb5:
+  return g()
+  Exception -> bb4
+  Jump -> bb3
+bb6:
+  raise
+  Exception -> bb3
+bb7:
+  Exception -> bb3
+"
+    );
+}
+
+#[test]
+fn match_and_with() {
+    let source = "\
+match p:
+    case 0:
+        with lock:
+            y = 1
+    case [a, *rest] if a:
+        y = 2
+    case _:
+        y = 3
+";
+    assert_eq!(
+        dump(source),
+        "\
+graph #0:
+bb0 (entry):
+  p
+  case 0:
+  True -> bb3
+  False -> bb4
+bb1 (exit):
+bb2:
+  Jump -> bb1
+bb3:
+  with lock:
+  y = 1
+  Jump -> bb2
+bb4:
+  case [a, *rest] if a:
+  True -> bb5
+  False -> bb6
+bb5:
+  y = 2
+  Jump -> bb2
+bb6:
+  case _:
+  Jump -> bb7
+bb7:
+  y = 3
+  Jump -> bb2
+"
+    );
+}
+
+#[test]
+fn unreachable_code() {
+    let source = "\
+def f(c):
+    if c:
+        return 1
+    else:
+        return 2
+raise E
+x = 1
+";
+    assert_eq!(
+        dump(source),
+        "\
+graph #0:
+bb0 (entry):
+  def f(c):
+  raise E
+  Exception -> bb1
+bb1 (exit):
+bb2:
+  x = 1
+  Jump -> bb1
+graph #1:
+bb0 (entry):
+  c
+  True -> bb2
+  False -> bb3
+bb1 (exit):
+bb2:
+  return 1
+  Jump -> bb1
+bb3:
+  return 2
+  Jump -> bb1
+"
+    );
+}
@@ -2,6 +2,8 @@
 pub struct TypeError {
     pub range: text_size::TextRange,
     pub message: String,
+    /// Additional information pointing at related code, e.g., at a declaration.
+    pub notes: Vec<Note>,
 }
 
 impl TypeError {
@@ -9,8 +11,22 @@ impl TypeError {
         Self {
             range,
             message: message.into(),
+            notes: Vec::new(),
         }
     }
+
+    pub fn with_note(mut self, range: text_size::TextRange, message: impl Into<String>) -> Self {
+        self.notes.push(Note {
+            range,
+            message: message.into(),
+        });
+        self
+    }
+}
+
+pub struct Note {
+    pub range: text_size::TextRange,
+    pub message: String,
 }
 
 #[must_use]
@@ -26,10 +42,7 @@ impl Errors {
     pub const ALL_GOOD: Self = Self::AllGood;
 
     pub fn single(range: text_size::TextRange, message: impl Into<String>) -> Self {
-        Self::Single(Box::new(TypeError {
-            range,
-            message: message.into(),
-        }))
+        Self::Single(Box::new(TypeError::new(range, message)))
     }
 }
 
@@ -49,10 +49,16 @@ impl IndexedModule {
     }
 
     /// Returns the node at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics for the index of the module itself, which is not a root node.
     pub fn get_by_index<'ast>(&'ast self, index: NodeIndex) -> AnyRootNodeRef<'ast> {
         // Note that this method restores the correct lifetime: the nodes are valid for as
         // long as the reference to `IndexedModule` is alive.
-        self.index[index.as_usize()]
+        //
+        // The module has index 0 and is not in `index`, the visited nodes start at 1.
+        self.index[index.as_usize() - 1]
     }
 }
 
@@ -1,8 +1,11 @@
+pub mod builtins;
+pub mod cfg;
 pub mod error;
 pub mod indexed;
 pub mod resolver;
 pub mod scope;
 pub mod symbol;
+pub mod types;
 
 trait HasId {
     fn id(&self) -> &ast::name::Name;
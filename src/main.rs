@@ -1,5 +1,7 @@
 use anyhow::bail;
-use xykpy::resolver::Resolver;
+use xykpy::resolver::{Resolution, Resolver, UnusedOptions};
+use xykpy::scope::ScopeId;
+use xykpy::types::{Inference, Inferrer};
 
 fn main() -> anyhow::Result<()> {
     let args = std::env::args().collect::<Vec<String>>();
@@ -12,23 +14,49 @@ fn main() -> anyhow::Result<()> {
     let module = xykpy::indexed::IndexedModule::new(parsed);
 
     let resolver = Resolver::new(module.syntax());
-    let outcome = resolver.run();
-    for error in outcome.errors {
+    let resolved = resolver.run();
+    let resolution = resolved.value;
+    let inferred = Inferrer::new(&module, &resolution).run();
+
+    for error in resolved.errors.into_iter().chain(inferred.errors) {
         println!("ERROR @ {:?}: {}", error.range, error.message);
+        for note in error.notes {
+            println!("  NOTE @ {:?}: {}", note.range, note.message);
+        }
     }
 
-    println!("{:#?}", outcome.value);
+    for error in resolution.unused(module.syntax(), &UnusedOptions::default()) {
+        println!("WARNING @ {:?}: {}", error.range, error.message);
+    }
 
-    // for (name, id) in scope.entries() {
-    //     let symbol = symbols.get(*id);
-    //     println!(
-    //         "{kind:?}({name}) @ {range:?}= {symbol:?}",
-    //         kind = symbol.kind,
-    //         name = name,
-    //         range = symbol.name_range,
-    //         symbol = symbol,
-    //     );
-    // }
+    let inference = inferred.value;
+    print_scope(&resolution, &inference, resolution.scopes().root_id(), 0);
 
     Ok(())
 }
+
+fn print_scope(resolution: &Resolution, inference: &Inference, id: ScopeId, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let scope = resolution.scopes().get(id);
+    println!("{indent}{:?} scope", scope.kind());
+    let mut symbols: Vec<_> = scope
+        .symbols()
+        .map(|(name, id)| (name, id, resolution.symbols().get(id)))
+        .collect();
+    symbols.sort_by_key(|(_, _, symbol)| symbol.name_range.start());
+    for (name, id, symbol) in symbols {
+        let ty = match inference.symbol_type(id) {
+            Some(ty) => format!(": {}", inference.types().display(ty)),
+            None => String::new(),
+        };
+        println!(
+            "{indent}  {kind}({name}{ty}) @ {range:?}, {references} references",
+            kind = symbol.kind,
+            range = symbol.name_range,
+            references = resolution.references(id).len(),
+        );
+    }
+    for child in scope.children() {
+        print_scope(resolution, inference, *child, depth + 1);
+    }
+}
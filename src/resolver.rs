@@ -2,50 +2,984 @@
 
 use std::collections::HashMap;
 
+use ast::HasNodeIndex;
+use ast::visitor::source_order::{SourceOrderVisitor, walk_expr, walk_pattern, walk_stmt};
+use enclosing::EnclosingScopes;
+use flow::{Binding, Flow, Loop};
 use lookup::{ScopeLookup, ScopeLoopkupBuilder};
+use text_size::{Ranged, TextRange, TextSize};
 
 use crate::{
-    error::{ErrorsBuilder, Outcome},
-    scope::ScopeTable,
-    symbol::{SymbolId, SymbolTable},
+    builtins::BUILTINS,
+    error::{ErrorsBuilder, Outcome, TypeError},
+    scope::{ScopeId, ScopeKind, ScopeTable},
+    symbol::{Access, Reference, Symbol, SymbolId, SymbolKind, SymbolTable, no_node_index},
 };
 
+mod enclosing;
+mod flow;
 mod lookup;
+mod suggest;
+#[cfg(test)]
+mod tests;
+mod unused;
 
+pub use unused::UnusedOptions;
+
+/// The result of name resolution: the scopes of a module, the symbols bound in them and the
+/// symbols every name refers to.
 #[derive(Debug)]
 pub struct Resolution {
     symbols: SymbolTable,
     scopes: ScopeTable,
+    /// Maps the loads of names to the symbols they resolve to.
     nodes: HashMap<ast::NodeIndex, SymbolId>,
+    /// Maps the names in bindings and declarations to the symbols they bind or declare.
+    bindings: HashMap<ast::NodeIndex, SymbolId>,
+    /// Maps `global` and `nonlocal` declarations to the symbols they refer to.
+    referents: HashMap<SymbolId, SymbolId>,
+    /// The references to every symbol, in source order.
+    references: HashMap<SymbolId, Vec<Reference>>,
+    /// The scopes the nodes are evaluated in.
+    enclosing: EnclosingScopes,
+}
+
+impl Resolution {
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    pub fn scopes(&self) -> &ScopeTable {
+        &self.scopes
+    }
+
+    /// Returns the scope created by `node`, e.g., by the module, a `def` statement or a
+    /// comprehension.
+    pub fn scope_created_by(&self, node: ast::NodeIndex) -> Option<ScopeId> {
+        self.scopes.get_by_node(node)
+    }
+
+    /// Returns the scope `node` is evaluated in. A `def` statement itself, its decorators and
+    /// its default values are evaluated in the enclosing scope, its parameters and its body in
+    /// the function scope. Annotations and class bases are evaluated in the annotation scope of
+    /// the type parameters, if there are any. The first iterable of a comprehension is evaluated
+    /// in the enclosing scope, everything else in the comprehension scope.
+    pub fn scope_of(&self, node: ast::NodeIndex) -> Option<ScopeId> {
+        self.enclosing.get(node)
+    }
+
+    /// Returns the symbol the name at `node` refers to. This works for loads of names as well as
+    /// for the names in bindings and declarations. Names declared `global` or `nonlocal` refer to
+    /// the symbols the declarations refer to.
+    pub fn symbol_at(&self, node: ast::NodeIndex) -> Option<SymbolId> {
+        self.nodes
+            .get(&node)
+            .or_else(|| self.bindings.get(&node))
+            .copied()
+    }
+
+    /// Returns the symbol a `global` or `nonlocal` declaration refers to, if there is one.
+    pub fn referent(&self, id: SymbolId) -> Option<SymbolId> {
+        self.referents.get(&id).copied()
+    }
+
+    /// Returns the references to the symbol in source order. References via `global` and
+    /// `nonlocal` declarations count as references to the symbols the declarations refer to.
+    pub fn references(&self, id: SymbolId) -> &[Reference] {
+        self.references.get(&id).map_or(&[], Vec::as_slice)
+    }
 }
 
 pub struct Resolver<'m> {
     module: &'m ast::ModModule,
     resolution: Resolution,
     errors: ErrorsBuilder,
+    builtins: HashMap<&'static str, SymbolId>,
     env: Vec<ScopeLookup<'m>>,
+    /// The loads that could not be resolved when we visited them, together with the closest
+    /// name that was visible at that point.
+    unresolved: Vec<(&'m ast::ExprName, Option<&'m str>)>,
+    /// The symbols that are bound at the current point of the evaluation.
+    flow: Flow,
+    /// The loops in the current scope we are currently in.
+    loops: Vec<Loop<'m>>,
+    /// Whether we are in an annotation. Annotations might be evaluated lazily.
+    in_annotation: bool,
 }
 
 impl<'m> Resolver<'m> {
     pub fn new(module: &'m ast::ModModule) -> Self {
-        let symbols = SymbolTable::new();
-        let scopes = ScopeTable::new(module.node_index.load());
+        let mut symbols = SymbolTable::new();
+        let mut scopes = ScopeTable::new(module.node_index.load());
+        let builtins_id = scopes.builtins_id();
+        let builtins = BUILTINS
+            .iter()
+            .map(|name| {
+                let id = symbols.insert(Symbol::builtin(builtins_id));
+                scopes.add_symbol(builtins_id, name, id);
+                (*name, id)
+            })
+            .collect();
         let nodes = HashMap::new();
-        let resolution = Resolution { symbols, scopes, nodes };
+        let bindings = HashMap::new();
+        let referents = HashMap::new();
+        let references = HashMap::new();
+        let resolution = Resolution {
+            symbols,
+            scopes,
+            nodes,
+            bindings,
+            referents,
+            references,
+            enclosing: EnclosingScopes::default(),
+        };
         let errors = ErrorsBuilder::new();
         let env = Vec::new();
-        Self { module, resolution, errors, env }
+        let unresolved = Vec::new();
+        Self {
+            module,
+            resolution,
+            errors,
+            builtins,
+            env,
+            unresolved,
+            flow: Flow::default(),
+            loops: Vec::new(),
+            in_annotation: false,
+        }
     }
 
     pub fn run(mut self) -> Outcome<Resolution> {
+        let module = self.module;
         let root_id = self.resolution.scopes.root_id();
-        let mut builder = ScopeLoopkupBuilder::new(
+        let mut builder = self.builder(root_id);
+        builder.add_block(&module.body);
+        let lookup = builder.build();
+        self.env.push(lookup);
+        self.visit_body(&module.body);
+        self.retry_unresolved();
+        self.env.pop();
+        for references in self.resolution.references.values_mut() {
+            references.sort_by_key(|reference| reference.range.start());
+        }
+        self.resolution.enclosing = EnclosingScopes::new(module, &self.resolution.scopes);
+        Outcome::mixed(self.resolution, self.errors)
+    }
+
+    fn builder(&mut self, scope_id: ScopeId) -> ScopeLoopkupBuilder<'m, '_> {
+        ScopeLoopkupBuilder::new(
             &mut self.resolution.symbols,
             &mut self.resolution.scopes,
             &mut self.errors,
-            root_id,
+            scope_id,
+        )
+    }
+
+    fn current_scope(&self) -> ScopeId {
+        self.env
+            .last()
+            .expect("There is always a scope.")
+            .scope_id()
+    }
+
+    /// Creates a new child scope of the current scope, lets `populate` collect its bindings and
+    /// makes it the current scope. Every call must be matched by a call to `exit_scope`.
+    fn enter_scope(
+        &mut self,
+        kind: ScopeKind,
+        node: impl HasNodeIndex,
+        populate: impl FnOnce(&mut ScopeLoopkupBuilder<'m, '_>),
+    ) {
+        let parent = self.current_scope();
+        let scope_id = self
+            .resolution
+            .scopes
+            .make_scope(kind, node.node_index().load(), parent);
+        let mut builder = self.builder(scope_id);
+        populate(&mut builder);
+        let lookup = builder.build();
+        self.link_declarations(&lookup);
+        self.env.push(lookup);
+    }
+
+    fn exit_scope(&mut self) {
+        self.env.pop();
+    }
+
+    /// Links the `global` and `nonlocal` declarations in `lookup` to the symbols they refer to.
+    fn link_declarations(&mut self, lookup: &ScopeLookup<'m>) {
+        let mut declarations: Vec<_> = lookup
+            .iter()
+            .map(|(name, id)| (name, id, *self.resolution.symbols.get(id)))
+            .filter(|(_, _, symbol)| {
+                matches!(symbol.kind, SymbolKind::Global | SymbolKind::Nonlocal)
+            })
+            .collect();
+        // Link in source order to keep the order of the errors stable.
+        declarations.sort_by_key(|(_, _, symbol)| symbol.name_range.start());
+        for (name, id, symbol) in declarations {
+            if symbol.kind == SymbolKind::Global {
+                self.link_global(name, id, &symbol);
+            } else {
+                self.link_nonlocal(name, id, &symbol);
+            }
+        }
+    }
+
+    /// Links a `global` declaration to the module-level symbol it refers to. If the module does
+    /// not bind the declared name itself but the declaring scope does, the binding in the
+    /// declaring scope creates the module-level symbol.
+    fn link_global(&mut self, name: &'m str, id: SymbolId, symbol: &Symbol) {
+        let root_id = self.resolution.scopes.root_id();
+        // The module scope is always at the bottom of the environment.
+        let module_lookup = &mut self.env[0];
+        let referent = match module_lookup.get(name) {
+            Some(referent) => referent,
+            None if symbol.is_defn() => {
+                let variable = Symbol {
+                    kind: SymbolKind::Variable,
+                    scope: root_id,
+                    decl: no_node_index(),
+                    ..*symbol
+                };
+                let referent = self.resolution.symbols.insert(variable);
+                self.resolution.scopes.add_symbol(root_id, name, referent);
+                module_lookup.insert(name, referent);
+                referent
+            }
+            None => return,
+        };
+        self.resolution.referents.insert(id, referent);
+    }
+
+    /// Links a `nonlocal` declaration to the symbol in the closest enclosing function scope that
+    /// binds the declared name. Class scopes are skipped, the module scope is not considered.
+    fn link_nonlocal(&mut self, name: &'m str, id: SymbolId, symbol: &Symbol) {
+        // The declaring scope has not been pushed onto the environment yet.
+        for lookup in self.env[1..].iter().rev() {
+            if self.resolution.scopes.get(lookup.scope_id()).kind() == ScopeKind::Class {
+                continue;
+            }
+            let Some(referent) = lookup.get(name) else {
+                continue;
+            };
+            match self.resolution.symbols.get(referent).kind {
+                // A name declared `global` in the enclosing scope is not bound there.
+                SymbolKind::Global => break,
+                SymbolKind::Nonlocal => {
+                    // If the enclosing declaration is invalid, we have reported that already.
+                    if let Some(referent) = self.resolution.referents.get(&referent).copied() {
+                        self.resolution.referents.insert(id, referent);
+                    }
+                    return;
+                }
+                _ => {
+                    self.resolution.referents.insert(id, referent);
+                    return;
+                }
+            }
+        }
+        self.errors.add(TypeError::new(
+            symbol.name_range,
+            format!("no binding for nonlocal '{name}' found"),
+        ));
+    }
+
+    /// Resolves the loads that could not be resolved when they were visited against the module
+    /// scope again. They might refer to module-level names that are only bound via `global`
+    /// declarations in functions visited later. The remaining ones are undefined.
+    fn retry_unresolved(&mut self) {
+        let module_lookup = &self.env[0];
+        for (name, suggestion) in std::mem::take(&mut self.unresolved) {
+            if let Some(id) = module_lookup.get(name.id.as_str()) {
+                self.resolution.nodes.insert(name.node_index.load(), id);
+                self.record(id, Reference::make(name, Access::Read));
+                continue;
+            }
+            let mut message = format!("name '{}' is not defined", name.id);
+            if let Some(suggestion) = suggestion {
+                message.push_str(&format!(". Did you mean: '{suggestion}'?"));
+            }
+            self.errors.add(TypeError::new(name.range, message));
+        }
+    }
+
+    /// Returns the lookups of the scopes visible from the current scope, innermost first. Class
+    /// scopes are only visible to their own body and to the annotation scopes directly inside
+    /// them.
+    fn visible_lookups(&self) -> impl Iterator<Item = &ScopeLookup<'m>> {
+        let in_annotation_scope =
+            self.resolution.scopes.get(self.current_scope()).kind() == ScopeKind::Annotation;
+        self.env
+            .iter()
+            .rev()
+            .enumerate()
+            .filter(move |(depth, lookup)| {
+                let visible = *depth == 0 || (*depth == 1 && in_annotation_scope);
+                visible || self.resolution.scopes.get(lookup.scope_id()).kind() != ScopeKind::Class
+            })
+            .map(|(_, lookup)| lookup)
+    }
+
+    /// Whether one of the scopes visible from the current scope contains a `from ... import *`.
+    fn has_star_import(&self) -> bool {
+        self.visible_lookups().any(|lookup| {
+            self.resolution
+                .scopes
+                .get(lookup.scope_id())
+                .has_star_import()
+        })
+    }
+
+    /// Looks `name` up in the current scope, then in the enclosing function scopes, then in the
+    /// module scope and finally in the builtins.
+    fn resolve(&self, name: &str) -> Option<SymbolId> {
+        for lookup in self.visible_lookups() {
+            if let Some(id) = lookup.get(name) {
+                match self.resolution.symbols.get(id).kind {
+                    SymbolKind::Nonlocal => {
+                        // If the declaration is invalid, we fall back to the enclosing scopes.
+                        if let Some(referent) = self.resolution.referents.get(&id) {
+                            return Some(*referent);
+                        }
+                    }
+                    SymbolKind::Global => {
+                        let referent = self.resolution.referents.get(&id).copied();
+                        return referent.or_else(|| self.builtins.get(name).copied());
+                    }
+                    _ => return Some(id),
+                }
+            }
+        }
+        self.builtins.get(name).copied()
+    }
+
+    /// Returns the visible name closest to the undefined `name`, if there is a plausible one.
+    /// Closer scopes win ties, the builtins come last.
+    fn suggest(&self, name: &str) -> Option<&'m str> {
+        let mut candidates = Vec::new();
+        for lookup in self.visible_lookups() {
+            let mut names: Vec<_> = lookup.iter().map(|(name, _)| name).collect();
+            names.sort_unstable();
+            candidates.extend(names);
+        }
+        candidates.extend(BUILTINS.iter().copied());
+        suggest::closest(name, candidates)
+    }
+
+    /// Returns the symbol a binding of `name` binds. Assignment expressions in comprehensions
+    /// bind names in an enclosing scope, so we look for the closest scope that binds the name.
+    fn binding_symbol(&self, name: &str) -> Option<SymbolId> {
+        self.env.iter().rev().find_map(|lookup| lookup.get(name))
+    }
+
+    /// Marks the symbol bound by a binding of `name` as bound and records the binding.
+    fn bind(&mut self, name: &str, reference: Reference) {
+        if let Some(id) = self.binding_symbol(name) {
+            self.flow.bind(id);
+            self.record(id, reference);
+        }
+    }
+
+    /// Records a declaration of `name` in the current scope.
+    fn declare(&mut self, name: &'m ast::Identifier) {
+        let lookup = self.env.last().expect("There is always a scope.");
+        if let Some(id) = lookup.get(name.id.as_str()) {
+            self.record(id, Reference::make(name, Access::Declare));
+        }
+    }
+
+    /// Records a reference to the symbol `id`. References via `global` and `nonlocal`
+    /// declarations are recorded for the symbols they refer to.
+    fn record(&mut self, id: SymbolId, reference: Reference) {
+        let id = self.resolution.referents.get(&id).copied().unwrap_or(id);
+        if reference.access != Access::Read {
+            self.resolution.bindings.insert(reference.node, id);
+        }
+        let references = self.resolution.references.entry(id).or_default();
+        references.push(reference);
+    }
+
+    /// Checks that a load of `name` in a module or function scope, which resolved to `id`, can
+    /// only happen after a definition of the name has executed. Loads of names bound in other
+    /// scopes are fine since function bodies are executed later.
+    fn check_defined(&mut self, name: &'m ast::ExprName, id: SymbolId) {
+        if self.in_annotation || !self.flow.is_reachable() {
+            return;
+        }
+        let current = self.current_scope();
+        let symbol = self.resolution.symbols.get(id);
+        let checked_scope = matches!(
+            self.resolution.scopes.get(current).kind(),
+            ScopeKind::Module | ScopeKind::Function
         );
-        builder.add_block(&self.module.body);
-        Outcome::mixed(self.resolution, self.errors)
+        let checked_kind = matches!(
+            symbol.kind,
+            SymbolKind::Variable
+                | SymbolKind::Function
+                | SymbolKind::Class
+                | SymbolKind::Import
+                | SymbolKind::Alias
+        );
+        if symbol.scope == current && checked_scope && checked_kind {
+            match self.flow.get(id) {
+                Binding::Bound => {}
+                Binding::Maybe(branch) => self.report_possibly_unbound(name, branch),
+                Binding::Unbound => self.defer_or_report_unbound(name, id),
+            }
+        }
+    }
+
+    fn report_possibly_unbound(&mut self, name: &ast::ExprName, branch: TextRange) {
+        let error = TypeError::new(
+            name.range,
+            format!("name '{}' is possibly unbound", name.id),
+        )
+        .with_note(branch, "it is not bound on the path through here");
+        self.errors.add(error);
+    }
+
+    fn defer_or_report_unbound(&mut self, name: &'m ast::ExprName, id: SymbolId) {
+        if let Some(loop_) = self.loops.last_mut() {
+            loop_.pending.push((name, id));
+            return;
+        }
+        // A binding, declaration or deletion earlier in the source did not reach the load, e.g.,
+        // because of a `del` statement or a `return` at the end of the binding branch.
+        let bound_earlier = self.resolution.references(id).iter().any(|reference| {
+            reference.access != Access::Read && reference.range.start() < name.range.start()
+        });
+        let message = if bound_earlier {
+            format!("name '{}' is unbound", name.id)
+        } else {
+            format!("name '{}' is used before it is defined", name.id)
+        };
+        self.errors.add(TypeError::new(name.range, message));
+    }
+
+    /// Runs `visit` on the part of a loop that can be executed repeatedly. Afterwards, the flow
+    /// is the one when the loop is exhausted. Returns the flows at the `break` statements.
+    fn visit_loop(
+        &mut self,
+        header: TextRange,
+        visit: impl FnOnce(&mut Self),
+    ) -> Vec<(Flow, TextRange)> {
+        let entry = self.flow.clone();
+        self.loops.push(Loop::default());
+        visit(self);
+        let Loop { pending, breaks, mut continues } =
+            self.loops.pop().expect("We pushed a loop above.");
+        continues.push((std::mem::take(&mut self.flow), header));
+        let back_edge = Flow::join(continues);
+        for (name, id) in pending {
+            match back_edge.get(id) {
+                // The first iteration does not bind the name but later ones might.
+                Binding::Bound | Binding::Maybe(_) => self.report_possibly_unbound(name, header),
+                Binding::Unbound => self.defer_or_report_unbound(name, id),
+            }
+        }
+        self.flow = Flow::join(Vec::from([(entry, header), (back_edge, header)]));
+        breaks
+    }
+
+    /// Visits `body` as a branch that might not be taken. Returns the flow at the end of the
+    /// branch and restores the flow from before the branch.
+    fn visit_branch(&mut self, body: &'m [ast::Stmt]) -> Flow {
+        let skipped = self.flow.clone();
+        self.visit_body(body);
+        std::mem::replace(&mut self.flow, skipped)
+    }
+
+    /// Whether `expr` calls a function that never returns, like `sys.exit()` or a function
+    /// annotated to return `NoReturn`.
+    fn is_no_return_call(&self, expr: &ast::Expr) -> bool {
+        let ast::Expr::Call(call) = expr else {
+            return false;
+        };
+        let symbol_of = |name: &ast::ExprName| {
+            let id = self.resolution.nodes.get(&name.node_index.load());
+            id.copied()
+        };
+        match &*call.func {
+            ast::Expr::Name(name) => symbol_of(name).is_some_and(|id| {
+                self.resolution.symbols.is_no_return(id)
+                    || ["exit", "quit"]
+                        .iter()
+                        .any(|builtin| self.builtins.get(builtin) == Some(&id))
+            }),
+            ast::Expr::Attribute(attribute) => {
+                let ast::Expr::Name(name) = &*attribute.value else {
+                    return false;
+                };
+                let Some(import) =
+                    symbol_of(name).and_then(|id| self.resolution.symbols.import(id))
+                else {
+                    return false;
+                };
+                import.member.is_none()
+                    && import.module.as_ref().is_some_and(|module| {
+                        matches!(
+                            (module.as_str(), attribute.attr.as_str()),
+                            ("sys", "exit") | ("os", "_exit")
+                        )
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    /// Runs `visit` in a new annotation scope binding `type_params`, if there are any. The bounds
+    /// and defaults of the type parameters are evaluated in the annotation scope too.
+    fn with_type_params(
+        &mut self,
+        type_params: Option<&'m ast::TypeParams>,
+        visit: impl FnOnce(&mut Self),
+    ) {
+        match type_params {
+            Some(type_params) => {
+                self.enter_scope(ScopeKind::Annotation, type_params, |builder| {
+                    builder.add_type_params(type_params);
+                });
+                for type_param in &type_params.type_params {
+                    let name = match type_param {
+                        ast::TypeParam::TypeVar(ast::TypeParamTypeVar { name, .. })
+                        | ast::TypeParam::ParamSpec(ast::TypeParamParamSpec { name, .. })
+                        | ast::TypeParam::TypeVarTuple(ast::TypeParamTypeVarTuple {
+                            name, ..
+                        }) => name,
+                    };
+                    self.declare(name);
+                }
+                self.visit_type_params(type_params);
+                visit(self);
+                self.exit_scope();
+            }
+            None => visit(self),
+        }
     }
+
+    /// Visits the default values of `parameters`. They are evaluated in the scope containing the
+    /// function or lambda definition.
+    fn visit_parameter_defaults(&mut self, parameters: &'m ast::Parameters) {
+        let ast::Parameters { posonlyargs, args, kwonlyargs, .. } = parameters;
+        for parameter in posonlyargs.iter().chain(args).chain(kwonlyargs) {
+            if let Some(default) = &parameter.default {
+                self.visit_expr(default);
+            }
+        }
+    }
+
+    /// Visits the annotations of `parameters`. They are evaluated in the scope containing the
+    /// function definition or in the annotation scope of its type parameters.
+    fn visit_parameter_annotations(&mut self, parameters: &'m ast::Parameters) {
+        for parameter in all_parameters(parameters) {
+            if let Some(annotation) = &parameter.annotation {
+                self.visit_annotation(annotation);
+            }
+        }
+    }
+
+    /// Records the declarations of `parameters` in the current scope.
+    fn declare_parameters(&mut self, parameters: &'m ast::Parameters) {
+        for parameter in all_parameters(parameters) {
+            self.declare(&parameter.name);
+        }
+    }
+
+    /// Visits a comprehension. The first iterable is evaluated in the enclosing scope, which
+    /// makes it the only part of a comprehension in a class body that can see the class scope.
+    /// Everything else is evaluated in the comprehension's own scope.
+    fn visit_comprehension_scope(
+        &mut self,
+        expr: &'m ast::Expr,
+        elts: &[&'m ast::Expr],
+        generators: &'m [ast::Comprehension],
+    ) {
+        let [first, rest @ ..] = generators else {
+            // Only possible after a syntax error.
+            walk_expr(self, expr);
+            return;
+        };
+        self.visit_expr(&first.iter);
+        self.enter_scope(ScopeKind::Comprehension, expr, |builder| {
+            builder.add_generators(generators)
+        });
+        // The comprehension might not iterate at all, so assignment expressions in it might not
+        // bind their targets.
+        let entry = self.flow.clone();
+        self.visit_expr(&first.target);
+        for condition in &first.ifs {
+            self.visit_expr(condition);
+        }
+        for generator in rest {
+            self.visit_comprehension(generator);
+        }
+        for elt in elts {
+            self.visit_expr(elt);
+        }
+        let iterated = std::mem::take(&mut self.flow);
+        self.flow = Flow::join(Vec::from([(entry, first.range), (iterated, first.range)]));
+        self.exit_scope();
+    }
+}
+
+impl<'m> SourceOrderVisitor<'m> for Resolver<'m> {
+    fn visit_stmt(&mut self, stmt: &'m ast::Stmt) {
+        match stmt {
+            // Only the body of a function or class is evaluated in its own scope. Decorators and
+            // default values are evaluated in the enclosing scope, annotations and class bases in
+            // the annotation scope of the type parameters, if there are any.
+            ast::Stmt::FunctionDef(func_def) => {
+                for decorator in &func_def.decorator_list {
+                    self.visit_decorator(decorator);
+                }
+                self.visit_parameter_defaults(&func_def.parameters);
+                self.with_type_params(func_def.type_params.as_deref(), |this| {
+                    this.visit_parameter_annotations(&func_def.parameters);
+                    if let Some(returns) = &func_def.returns {
+                        this.visit_annotation(returns);
+                    }
+                    this.enter_scope(ScopeKind::Function, func_def, |builder| {
+                        builder.add_parameters(&func_def.parameters);
+                        builder.add_block(&func_def.body);
+                    });
+                    this.declare_parameters(&func_def.parameters);
+                    // The body is executed later and loops around the definition do not execute
+                    // it repeatedly.
+                    let flow = std::mem::take(&mut this.flow);
+                    let loops = std::mem::take(&mut this.loops);
+                    this.visit_body(&func_def.body);
+                    this.flow = flow;
+                    this.loops = loops;
+                    this.exit_scope();
+                });
+                let reference = Reference::make(&func_def.name, Access::Declare);
+                self.bind(func_def.name.id.as_str(), reference);
+            }
+            ast::Stmt::ClassDef(class_def) => {
+                for decorator in &class_def.decorator_list {
+                    self.visit_decorator(decorator);
+                }
+                self.with_type_params(class_def.type_params.as_deref(), |this| {
+                    if let Some(arguments) = &class_def.arguments {
+                        this.visit_arguments(arguments);
+                    }
+                    this.enter_scope(ScopeKind::Class, class_def, |builder| {
+                        builder.add_block(&class_def.body);
+                    });
+                    this.visit_body(&class_def.body);
+                    this.exit_scope();
+                });
+                let reference = Reference::make(&class_def.name, Access::Declare);
+                self.bind(class_def.name.id.as_str(), reference);
+            }
+            ast::Stmt::TypeAlias(alias_def) => {
+                // The value of a type alias is evaluated lazily, like an annotation.
+                self.with_type_params(alias_def.type_params.as_deref(), |this| {
+                    this.visit_annotation(&alias_def.value);
+                });
+                if let ast::Expr::Name(name) = &*alias_def.name {
+                    let reference = Reference::make(name, Access::Declare);
+                    self.bind(name.id.as_str(), reference);
+                }
+            }
+            // The targets of assignments are bound after the value has been evaluated.
+            ast::Stmt::Assign(assign) => {
+                self.visit_expr(&assign.value);
+                for target in &assign.targets {
+                    self.visit_expr(target);
+                }
+            }
+            ast::Stmt::AnnAssign(assign) => {
+                self.visit_annotation(&assign.annotation);
+                if let Some(value) = &assign.value {
+                    self.visit_expr(value);
+                }
+                // An annotation without a value does not bind the name.
+                match &*assign.target {
+                    ast::Expr::Name(name) if assign.value.is_none() => {
+                        if let Some(id) = self.binding_symbol(name.id.as_str()) {
+                            self.record(id, Reference::make(name, Access::Declare));
+                        }
+                    }
+                    target => self.visit_expr(target),
+                }
+            }
+            ast::Stmt::AugAssign(assign) => {
+                // The target of an augmented assignment is read before it is written.
+                if let ast::Expr::Name(name) = &*assign.target
+                    && let Some(id) = self.resolve(name.id.as_str())
+                {
+                    self.check_defined(name, id);
+                    self.record(id, Reference::make(name, Access::Read));
+                }
+                walk_stmt(self, stmt);
+            }
+            ast::Stmt::For(for_stmt) => {
+                self.visit_expr(&for_stmt.iter);
+                let header = for_stmt.target.range();
+                let mut branches = self.visit_loop(header, |this| {
+                    this.visit_expr(&for_stmt.target);
+                    this.visit_body(&for_stmt.body);
+                });
+                self.visit_body(&for_stmt.orelse);
+                branches.push((std::mem::take(&mut self.flow), header));
+                self.flow = Flow::join(branches);
+            }
+            ast::Stmt::While(while_stmt) => {
+                let header = while_stmt.test.range();
+                let mut branches = self.visit_loop(header, |this| {
+                    this.visit_expr(&while_stmt.test);
+                    this.visit_body(&while_stmt.body);
+                });
+                // `while True:` can only be left with a `break`.
+                if matches!(&*while_stmt.test, ast::Expr::BooleanLiteral(literal) if literal.value)
+                {
+                    self.flow.terminate();
+                }
+                self.visit_body(&while_stmt.orelse);
+                branches.push((std::mem::take(&mut self.flow), header));
+                self.flow = Flow::join(branches);
+            }
+            ast::Stmt::If(if_stmt) => {
+                self.visit_expr(&if_stmt.test);
+                let mut branches =
+                    Vec::from([(self.visit_branch(&if_stmt.body), if_stmt.test.range())]);
+                // Without an `else` clause, the flow can skip all branches after the last test.
+                let mut last_test = Some(if_stmt.test.range());
+                for clause in &if_stmt.elif_else_clauses {
+                    let range = match &clause.test {
+                        Some(test) => {
+                            self.visit_expr(test);
+                            last_test = Some(test.range());
+                            test.range()
+                        }
+                        None => {
+                            last_test = None;
+                            clause.range
+                        }
+                    };
+                    branches.push((self.visit_branch(&clause.body), range));
+                }
+                if let Some(range) = last_test {
+                    branches.push((std::mem::take(&mut self.flow), range));
+                }
+                self.flow = Flow::join(branches);
+            }
+            ast::Stmt::Match(match_stmt) => {
+                self.visit_expr(&match_stmt.subject);
+                let mut branches = Vec::new();
+                let mut exhaustive = false;
+                for case in &match_stmt.cases {
+                    let skipped = self.flow.clone();
+                    self.visit_match_case(case);
+                    branches.push((std::mem::replace(&mut self.flow, skipped), case.range));
+                    exhaustive |= case.guard.is_none()
+                        && matches!(
+                            case.pattern,
+                            ast::Pattern::MatchAs(ast::PatternMatchAs { pattern: None, .. })
+                        );
+                }
+                if !exhaustive {
+                    branches.push((std::mem::take(&mut self.flow), match_stmt.subject.range()));
+                }
+                self.flow = Flow::join(branches);
+            }
+            ast::Stmt::Try(try_stmt) => {
+                let body_range = match (try_stmt.body.first(), try_stmt.body.last()) {
+                    (Some(first), Some(last)) => TextRange::new(first.start(), last.end()),
+                    _ => try_stmt.range,
+                };
+                let entry = self.flow.clone();
+                self.visit_body(&try_stmt.body);
+                // An exception can leave the body at any point. We approximate the flow at that
+                // point by the flows before and after the body.
+                let completed = self.flow.clone().revive();
+                let raised = |branch: TextRange| {
+                    Flow::join(Vec::from([
+                        (entry.clone(), branch),
+                        (completed.clone(), body_range),
+                    ]))
+                };
+                self.visit_body(&try_stmt.orelse);
+                let mut branches = Vec::from([(std::mem::take(&mut self.flow), body_range)]);
+                for handler in &try_stmt.handlers {
+                    self.flow = raised(handler.range());
+                    self.visit_except_handler(handler);
+                    branches.push((std::mem::take(&mut self.flow), handler.range()));
+                }
+                self.flow = Flow::join(branches);
+                if self.flow.is_reachable() {
+                    self.visit_body(&try_stmt.finalbody);
+                } else {
+                    // The `finally` clause is also executed when all paths leave the statement.
+                    self.flow = raised(body_range);
+                    self.visit_body(&try_stmt.finalbody);
+                    self.flow.terminate();
+                }
+            }
+            ast::Stmt::Return(_) | ast::Stmt::Raise(_) => {
+                walk_stmt(self, stmt);
+                self.flow.terminate();
+            }
+            ast::Stmt::Break(_) | ast::Stmt::Continue(_) => {
+                let flow = self.flow.clone();
+                if let Some(loop_) = self.loops.last_mut() {
+                    if stmt.is_break_stmt() {
+                        loop_.breaks.push((flow, stmt.range()));
+                    } else {
+                        loop_.continues.push((flow, stmt.range()));
+                    }
+                }
+                self.flow.terminate();
+            }
+            ast::Stmt::Expr(expr_stmt) => {
+                self.visit_expr(&expr_stmt.value);
+                if self.is_no_return_call(&expr_stmt.value) {
+                    self.flow.terminate();
+                }
+            }
+            ast::Stmt::Import(import) => {
+                for alias in &import.names {
+                    match &alias.asname {
+                        Some(asname) => {
+                            let reference = Reference::make(asname, Access::Write);
+                            self.bind(asname.id.as_str(), reference);
+                        }
+                        // `import a.b.c` binds the top-level package `a`.
+                        None => {
+                            let id = alias.name.id.as_str();
+                            let package = id.split_once('.').map_or(id, |(package, _)| package);
+                            let range = TextRange::at(alias.name.start(), TextSize::of(package));
+                            let reference = Reference {
+                                range,
+                                ..Reference::make(&alias.name, Access::Write)
+                            };
+                            self.bind(package, reference);
+                        }
+                    }
+                }
+            }
+            ast::Stmt::ImportFrom(import) => {
+                for alias in &import.names {
+                    let name = alias.asname.as_ref().unwrap_or(&alias.name);
+                    self.bind(name.id.as_str(), Reference::make(name, Access::Write));
+                }
+            }
+            ast::Stmt::Global(ast::StmtGlobal { names, .. })
+            | ast::Stmt::Nonlocal(ast::StmtNonlocal { names, .. }) => {
+                for name in names {
+                    self.declare(name);
+                }
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'m ast::Expr) {
+        match expr {
+            ast::Expr::Lambda(lambda) => {
+                if let Some(parameters) = &lambda.parameters {
+                    self.visit_parameter_defaults(parameters);
+                }
+                self.enter_scope(ScopeKind::Lambda, lambda, |builder| {
+                    if let Some(parameters) = &lambda.parameters {
+                        builder.add_parameters(parameters);
+                    }
+                    builder.visit_expr(&lambda.body);
+                });
+                if let Some(parameters) = &lambda.parameters {
+                    self.declare_parameters(parameters);
+                }
+                self.visit_expr(&lambda.body);
+                self.exit_scope();
+            }
+            ast::Expr::ListComp(ast::ExprListComp { elt, generators, .. })
+            | ast::Expr::SetComp(ast::ExprSetComp { elt, generators, .. })
+            | ast::Expr::Generator(ast::ExprGenerator { elt, generators, .. }) => {
+                self.visit_comprehension_scope(expr, &[&**elt], generators);
+            }
+            ast::Expr::DictComp(ast::ExprDictComp { key, value, generators, .. }) => {
+                self.visit_comprehension_scope(expr, &[&**key, &**value], generators);
+            }
+            ast::Expr::Named(named) => {
+                self.visit_expr(&named.value);
+                self.visit_expr(&named.target);
+            }
+            ast::Expr::Name(name) => match name.ctx {
+                ast::ExprContext::Load => match self.resolve(name.id.as_str()) {
+                    Some(id) => {
+                        self.resolution.nodes.insert(name.node_index.load(), id);
+                        self.record(id, Reference::make(name, Access::Read));
+                        self.check_defined(name, id);
+                    }
+                    // The name might be bound by a `from ... import *`.
+                    None if self.has_star_import() => {}
+                    None => {
+                        let suggestion = self.suggest(name.id.as_str());
+                        self.unresolved.push((name, suggestion));
+                    }
+                },
+                ast::ExprContext::Store => {
+                    self.bind(name.id.as_str(), Reference::make(name, Access::Write));
+                }
+                ast::ExprContext::Del => {
+                    if let Some(id) = self.binding_symbol(name.id.as_str()) {
+                        self.flow.unbind(id);
+                        self.record(id, Reference::make(name, Access::Delete));
+                    }
+                }
+                ast::ExprContext::Invalid => {}
+            },
+            _ => walk_expr(self, expr),
+        }
+    }
+
+    fn visit_annotation(&mut self, expr: &'m ast::Expr) {
+        let in_annotation = std::mem::replace(&mut self.in_annotation, true);
+        self.visit_expr(expr);
+        self.in_annotation = in_annotation;
+    }
+
+    fn visit_except_handler(&mut self, handler: &'m ast::ExceptHandler) {
+        let ast::ExceptHandler::ExceptHandler(handler) = handler;
+        if let Some(type_) = &handler.type_ {
+            self.visit_expr(type_);
+        }
+        if let Some(name) = &handler.name {
+            self.bind(name.id.as_str(), Reference::make(name, Access::Write));
+        }
+        self.visit_body(&handler.body);
+    }
+
+    fn visit_pattern(&mut self, pattern: &'m ast::Pattern) {
+        walk_pattern(self, pattern);
+        match pattern {
+            ast::Pattern::MatchAs(ast::PatternMatchAs { name: Some(name), .. })
+            | ast::Pattern::MatchStar(ast::PatternMatchStar { name: Some(name), .. })
+            | ast::Pattern::MatchMapping(ast::PatternMatchMapping { rest: Some(name), .. }) => {
+                self.bind(name.id.as_str(), Reference::make(name, Access::Write));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the parameters in source order.
+fn all_parameters(parameters: &ast::Parameters) -> impl Iterator<Item = &ast::Parameter> {
+    let ast::Parameters {
+        posonlyargs,
+        args,
+        vararg,
+        kwonlyargs,
+        kwarg,
+        ..
+    } = parameters;
+    posonlyargs
+        .iter()
+        .chain(args)
+        .map(|parameter| &parameter.parameter)
+        .chain(vararg.as_deref())
+        .chain(kwonlyargs.iter().map(|parameter| &parameter.parameter))
+        .chain(kwarg.as_deref())
 }
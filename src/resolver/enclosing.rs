@@ -0,0 +1,359 @@
+use ast::HasNodeIndex;
+use ast::visitor::source_order::*;
+
+use crate::scope::{ScopeId, ScopeTable};
+
+/// Maps every node of a module to the scope it is evaluated in. Nodes are indexed in source
+/// order and most nodes are evaluated in the same scope as the node before them, so we only
+/// store the first node of every run of nodes evaluated in the same scope.
+#[derive(Debug, Default)]
+pub(super) struct EnclosingScopes {
+    /// The first node of every run together with the scope of the run, in source order.
+    runs: Vec<(usize, ScopeId)>,
+    /// The number of nodes in the module.
+    len: usize,
+}
+
+impl EnclosingScopes {
+    /// Collects the scopes the nodes of `module` are evaluated in. `scopes` must contain the
+    /// scopes of `module`.
+    pub(super) fn new(module: &ast::ModModule, scopes: &ScopeTable) -> Self {
+        let mut builder = Builder {
+            scopes,
+            current: scopes.root_id(),
+            enclosing: Self::default(),
+        };
+        builder.mark(module);
+        builder.visit_body(&module.body);
+        builder.enclosing
+    }
+
+    pub(super) fn get(&self, node: ast::NodeIndex) -> Option<ScopeId> {
+        let index = node.as_usize();
+        if index >= self.len {
+            return None;
+        }
+        let run = self.runs.partition_point(|(start, _)| *start <= index);
+        Some(self.runs[run - 1].1)
+    }
+}
+
+/// Visits the nodes in the same order as [`crate::indexed::Visitor`] and keeps track of the
+/// scope they are evaluated in. The rules are the same as in the resolver.
+struct Builder<'s> {
+    scopes: &'s ScopeTable,
+    current: ScopeId,
+    enclosing: EnclosingScopes,
+}
+
+impl Builder<'_> {
+    fn mark(&mut self, node: &impl HasNodeIndex) {
+        let index = node.node_index().load().as_usize();
+        let runs = &mut self.enclosing.runs;
+        if runs.last().is_none_or(|(_, scope)| *scope != self.current) {
+            runs.push((index, self.current));
+        }
+        self.enclosing.len = self.enclosing.len.max(index + 1);
+    }
+
+    fn in_scope(&mut self, scope: ScopeId, visit: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.current, scope);
+        visit(self);
+        self.current = outer;
+    }
+
+    /// Returns the scope created by `node`. The resolver does not create scopes for some
+    /// invalid syntax, e.g., comprehensions without generators. Their parts are evaluated in the
+    /// current scope then.
+    fn scope_created_by(&self, node: &impl HasNodeIndex) -> ScopeId {
+        self.scopes
+            .get_by_node(node.node_index().load())
+            .unwrap_or(self.current)
+    }
+
+    /// Visits `type_params`, if there are any, in their annotation scope and returns the scope
+    /// the annotations of the definition they belong to are evaluated in.
+    fn visit_type_params_scope(&mut self, type_params: Option<&ast::TypeParams>) -> ScopeId {
+        match type_params {
+            Some(type_params) => {
+                let scope = self.scope_created_by(type_params);
+                self.in_scope(scope, |this| this.visit_type_params(type_params));
+                scope
+            }
+            None => self.current,
+        }
+    }
+
+    /// Visits the `parameters` of a function or lambda. The parameters themselves belong to the
+    /// scope of the `function`, their annotations to the `annotations` scope and their default
+    /// values to the current scope.
+    fn visit_signature(
+        &mut self,
+        parameters: &ast::Parameters,
+        annotations: ScopeId,
+        function: ScopeId,
+    ) {
+        let ast::Parameters {
+            posonlyargs,
+            args,
+            vararg,
+            kwonlyargs,
+            kwarg,
+            ..
+        } = parameters;
+        self.in_scope(function, |this| this.mark(parameters));
+        for parameter in posonlyargs.iter().chain(args) {
+            self.visit_signature_parameter_with_default(parameter, annotations, function);
+        }
+        if let Some(parameter) = vararg {
+            self.visit_signature_parameter(parameter, annotations, function);
+        }
+        for parameter in kwonlyargs {
+            self.visit_signature_parameter_with_default(parameter, annotations, function);
+        }
+        if let Some(parameter) = kwarg {
+            self.visit_signature_parameter(parameter, annotations, function);
+        }
+    }
+
+    fn visit_signature_parameter_with_default(
+        &mut self,
+        parameter: &ast::ParameterWithDefault,
+        annotations: ScopeId,
+        function: ScopeId,
+    ) {
+        self.in_scope(function, |this| this.mark(parameter));
+        self.visit_signature_parameter(&parameter.parameter, annotations, function);
+        if let Some(default) = &parameter.default {
+            self.visit_expr(default);
+        }
+    }
+
+    fn visit_signature_parameter(
+        &mut self,
+        parameter: &ast::Parameter,
+        annotations: ScopeId,
+        function: ScopeId,
+    ) {
+        self.in_scope(function, |this| {
+            this.mark(parameter);
+            this.visit_identifier(&parameter.name);
+        });
+        if let Some(annotation) = &parameter.annotation {
+            self.in_scope(annotations, |this| this.visit_annotation(annotation));
+        }
+    }
+
+    /// Visits a comprehension. The first iterable is evaluated in the current scope, everything
+    /// else in the comprehension's own scope.
+    fn visit_comprehension_scope(
+        &mut self,
+        expr: &ast::Expr,
+        elts: &[&ast::Expr],
+        generators: &[ast::Comprehension],
+    ) {
+        let outer = self.current;
+        let scope = self.scope_created_by(expr);
+        self.in_scope(scope, |this| {
+            for elt in elts {
+                this.visit_expr(elt);
+            }
+            for (i, generator) in generators.iter().enumerate() {
+                this.mark(generator);
+                this.visit_expr(&generator.target);
+                if i == 0 {
+                    this.in_scope(outer, |this| this.visit_expr(&generator.iter));
+                } else {
+                    this.visit_expr(&generator.iter);
+                }
+                for condition in &generator.ifs {
+                    this.visit_expr(condition);
+                }
+            }
+        });
+    }
+}
+
+impl<'a> SourceOrderVisitor<'a> for Builder<'_> {
+    fn visit_stmt(&mut self, stmt: &'a ast::Stmt) {
+        self.mark(stmt);
+        match stmt {
+            ast::Stmt::FunctionDef(func_def) => {
+                for decorator in &func_def.decorator_list {
+                    self.visit_decorator(decorator);
+                }
+                self.visit_identifier(&func_def.name);
+                let annotations = self.visit_type_params_scope(func_def.type_params.as_deref());
+                let function = self.scope_created_by(func_def);
+                self.visit_signature(&func_def.parameters, annotations, function);
+                if let Some(returns) = &func_def.returns {
+                    self.in_scope(annotations, |this| this.visit_annotation(returns));
+                }
+                self.in_scope(function, |this| this.visit_body(&func_def.body));
+            }
+            ast::Stmt::ClassDef(class_def) => {
+                for decorator in &class_def.decorator_list {
+                    self.visit_decorator(decorator);
+                }
+                self.visit_identifier(&class_def.name);
+                let annotations = self.visit_type_params_scope(class_def.type_params.as_deref());
+                if let Some(arguments) = &class_def.arguments {
+                    self.in_scope(annotations, |this| this.visit_arguments(arguments));
+                }
+                let class = self.scope_created_by(class_def);
+                self.in_scope(class, |this| this.visit_body(&class_def.body));
+            }
+            ast::Stmt::TypeAlias(alias_def) => {
+                self.visit_expr(&alias_def.name);
+                let annotations = self.visit_type_params_scope(alias_def.type_params.as_deref());
+                self.in_scope(annotations, |this| this.visit_expr(&alias_def.value));
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'a ast::Expr) {
+        self.mark(expr);
+        match expr {
+            ast::Expr::Lambda(lambda) => {
+                let scope = self.scope_created_by(lambda);
+                if let Some(parameters) = &lambda.parameters {
+                    self.visit_signature(parameters, scope, scope);
+                }
+                self.in_scope(scope, |this| this.visit_expr(&lambda.body));
+            }
+            ast::Expr::ListComp(ast::ExprListComp { elt, generators, .. })
+            | ast::Expr::SetComp(ast::ExprSetComp { elt, generators, .. })
+            | ast::Expr::Generator(ast::ExprGenerator { elt, generators, .. }) => {
+                self.visit_comprehension_scope(expr, &[&**elt], generators);
+            }
+            ast::Expr::DictComp(ast::ExprDictComp { key, value, generators, .. }) => {
+                self.visit_comprehension_scope(expr, &[&**key, &**value], generators);
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+
+    fn visit_annotation(&mut self, expr: &'a ast::Expr) {
+        self.mark(expr);
+        walk_annotation(self, expr);
+    }
+
+    fn visit_decorator(&mut self, decorator: &'a ast::Decorator) {
+        self.mark(decorator);
+        walk_decorator(self, decorator);
+    }
+
+    fn visit_comprehension(&mut self, comprehension: &'a ast::Comprehension) {
+        self.mark(comprehension);
+        walk_comprehension(self, comprehension);
+    }
+
+    fn visit_except_handler(&mut self, except_handler: &'a ast::ExceptHandler) {
+        self.mark(except_handler);
+        walk_except_handler(self, except_handler);
+    }
+
+    fn visit_arguments(&mut self, arguments: &'a ast::Arguments) {
+        self.mark(arguments);
+        walk_arguments(self, arguments);
+    }
+
+    fn visit_parameters(&mut self, parameters: &'a ast::Parameters) {
+        self.mark(parameters);
+        walk_parameters(self, parameters);
+    }
+
+    fn visit_parameter(&mut self, parameter: &'a ast::Parameter) {
+        self.mark(parameter);
+        walk_parameter(self, parameter);
+    }
+
+    fn visit_parameter_with_default(&mut self, parameter: &'a ast::ParameterWithDefault) {
+        self.mark(parameter);
+        walk_parameter_with_default(self, parameter);
+    }
+
+    fn visit_keyword(&mut self, keyword: &'a ast::Keyword) {
+        self.mark(keyword);
+        walk_keyword(self, keyword);
+    }
+
+    fn visit_alias(&mut self, alias: &'a ast::Alias) {
+        self.mark(alias);
+        walk_alias(self, alias);
+    }
+
+    fn visit_with_item(&mut self, with_item: &'a ast::WithItem) {
+        self.mark(with_item);
+        walk_with_item(self, with_item);
+    }
+
+    fn visit_type_params(&mut self, type_params: &'a ast::TypeParams) {
+        self.mark(type_params);
+        walk_type_params(self, type_params);
+    }
+
+    fn visit_type_param(&mut self, type_param: &'a ast::TypeParam) {
+        self.mark(type_param);
+        walk_type_param(self, type_param);
+    }
+
+    fn visit_match_case(&mut self, match_case: &'a ast::MatchCase) {
+        self.mark(match_case);
+        walk_match_case(self, match_case);
+    }
+
+    fn visit_pattern(&mut self, pattern: &'a ast::Pattern) {
+        self.mark(pattern);
+        walk_pattern(self, pattern);
+    }
+
+    fn visit_pattern_arguments(&mut self, pattern_arguments: &'a ast::PatternArguments) {
+        self.mark(pattern_arguments);
+        walk_pattern_arguments(self, pattern_arguments);
+    }
+
+    fn visit_pattern_keyword(&mut self, pattern_keyword: &'a ast::PatternKeyword) {
+        self.mark(pattern_keyword);
+        walk_pattern_keyword(self, pattern_keyword);
+    }
+
+    fn visit_elif_else_clause(&mut self, elif_else_clause: &'a ast::ElifElseClause) {
+        self.mark(elif_else_clause);
+        walk_elif_else_clause(self, elif_else_clause);
+    }
+
+    fn visit_f_string(&mut self, f_string: &'a ast::FString) {
+        self.mark(f_string);
+        walk_f_string(self, f_string);
+    }
+
+    fn visit_interpolated_string_element(
+        &mut self,
+        interpolated_string_element: &'a ast::InterpolatedStringElement,
+    ) {
+        self.mark(interpolated_string_element);
+        walk_interpolated_string_element(self, interpolated_string_element);
+    }
+
+    fn visit_t_string(&mut self, t_string: &'a ast::TString) {
+        self.mark(t_string);
+        walk_t_string(self, t_string);
+    }
+
+    fn visit_string_literal(&mut self, string_literal: &'a ast::StringLiteral) {
+        self.mark(string_literal);
+        walk_string_literal(self, string_literal);
+    }
+
+    fn visit_bytes_literal(&mut self, bytes_literal: &'a ast::BytesLiteral) {
+        self.mark(bytes_literal);
+        walk_bytes_literal(self, bytes_literal);
+    }
+
+    fn visit_identifier(&mut self, identifier: &'a ast::Identifier) {
+        self.mark(identifier);
+        walk_identifier(self, identifier);
+    }
+}
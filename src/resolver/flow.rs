@@ -0,0 +1,111 @@
+use std::collections::{HashMap, HashSet};
+
+use text_size::TextRange;
+
+use crate::symbol::SymbolId;
+
+/// Whether a symbol is bound at some point of the evaluation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Binding {
+    Bound,
+    /// The symbol is not bound on the path through the branch at the given range.
+    Maybe(TextRange),
+    Unbound,
+}
+
+/// The symbols that are bound at some point of the evaluation.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Flow {
+    /// Whether no path reaches this point, e.g., because it follows a `return`.
+    unreachable: bool,
+    /// The symbols bound on all paths reaching this point.
+    bound: HashSet<SymbolId>,
+    /// The symbols bound on some but not all paths reaching this point, together with the range
+    /// of a branch on a path that does not bind them.
+    maybe: HashMap<SymbolId, TextRange>,
+}
+
+impl Flow {
+    pub(super) fn is_reachable(&self) -> bool {
+        !self.unreachable
+    }
+
+    /// Marks this point as unreachable, e.g., after a `return` or `raise`.
+    pub(super) fn terminate(&mut self) {
+        self.unreachable = true;
+    }
+
+    /// Returns the same flow but reachable. We use this for paths we do not track precisely, like
+    /// the ones leaving a `try` body because of an exception.
+    pub(super) fn revive(mut self) -> Self {
+        self.unreachable = false;
+        self
+    }
+
+    pub(super) fn bind(&mut self, id: SymbolId) {
+        self.bound.insert(id);
+        self.maybe.remove(&id);
+    }
+
+    /// Marks the symbol as unbound, e.g., after a `del` statement.
+    pub(super) fn unbind(&mut self, id: SymbolId) {
+        self.bound.remove(&id);
+        self.maybe.remove(&id);
+    }
+
+    pub(super) fn get(&self, id: SymbolId) -> Binding {
+        if self.bound.contains(&id) {
+            Binding::Bound
+        } else if let Some(range) = self.maybe.get(&id) {
+            Binding::Maybe(*range)
+        } else {
+            Binding::Unbound
+        }
+    }
+
+    /// Joins the flows at the ends of several branches. Each flow comes with the range of the
+    /// branch it went through. Unreachable branches do not contribute to the result.
+    pub(super) fn join(branches: Vec<(Flow, TextRange)>) -> Flow {
+        let reachable: Vec<_> = branches
+            .iter()
+            .filter(|(flow, _)| flow.is_reachable())
+            .collect();
+        let Some(((first, _), rest)) = reachable.split_first() else {
+            return Flow { unreachable: true, ..Flow::default() };
+        };
+        let bound: HashSet<_> = first
+            .bound
+            .iter()
+            .filter(|id| rest.iter().all(|(flow, _)| flow.bound.contains(id)))
+            .copied()
+            .collect();
+        let mut maybe = HashMap::new();
+        for (flow, _) in &reachable {
+            for id in flow.bound.iter().chain(flow.maybe.keys()) {
+                if bound.contains(id) || maybe.contains_key(id) {
+                    continue;
+                }
+                // Find a branch on a path that does not bind the symbol.
+                let range = reachable
+                    .iter()
+                    .find(|(flow, _)| !flow.bound.contains(id))
+                    .map(|(flow, range)| flow.maybe.get(id).copied().unwrap_or(*range))
+                    .expect("The symbol is not bound on all paths.");
+                maybe.insert(*id, range);
+            }
+        }
+        Flow { unreachable: false, bound, maybe }
+    }
+}
+
+/// The state of a loop we are currently in.
+#[derive(Default)]
+pub(super) struct Loop<'m> {
+    /// The loads of names that were not bound yet when we visited them. An earlier iteration of
+    /// the loop might have bound them.
+    pub(super) pending: Vec<(&'m ast::ExprName, SymbolId)>,
+    /// The flows at the `break` statements of the loop, together with their ranges.
+    pub(super) breaks: Vec<(Flow, TextRange)>,
+    /// The flows at the `continue` statements of the loop, together with their ranges.
+    pub(super) continues: Vec<(Flow, TextRange)>,
+}
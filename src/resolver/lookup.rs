@@ -1,23 +1,59 @@
 use std::collections::HashMap;
 
 use ast::HasNodeIndex;
-use text_size::Ranged;
+use ast::visitor::source_order::{SourceOrderVisitor, walk_expr, walk_pattern, walk_stmt};
+use text_size::{Ranged, TextRange, TextSize};
 
 use crate::{
     HasId,
     error::{ErrorsBuilder, TypeError},
-    scope::{ScopeId, ScopeTable},
-    symbol::{DeclOrDefn, Symbol, SymbolId, SymbolKind, SymbolTable},
+    scope::{ScopeId, ScopeKind, ScopeTable},
+    symbol::{
+        Conflict, DeclOrDefn, Import, Parameter, ParameterKind, Symbol, SymbolId, SymbolKind,
+        SymbolTable, no_node_index,
+    },
 };
 
-pub struct ScopeLookup<'m>(HashMap<&'m ast::name::Name, SymbolId>);
+pub struct ScopeLookup<'m> {
+    scope_id: ScopeId,
+    symbols: HashMap<&'m str, SymbolId>,
+}
+
+impl<'m> ScopeLookup<'m> {
+    pub(super) fn scope_id(&self) -> ScopeId {
+        self.scope_id
+    }
+
+    pub(super) fn get(&self, name: &str) -> Option<SymbolId> {
+        self.symbols.get(name).copied()
+    }
+
+    pub(super) fn insert(&mut self, name: &'m str, id: SymbolId) {
+        self.symbols.insert(name, id);
+    }
+
+    pub(super) fn iter(&self) -> impl Iterator<Item = (&'m str, SymbolId)> {
+        self.symbols.iter().map(|(name, id)| (*name, *id))
+    }
+}
 
 pub(super) struct ScopeLoopkupBuilder<'m, 's> {
     symbols: &'s mut SymbolTable,
     scopes: &'s mut ScopeTable,
     errors: &'s mut ErrorsBuilder,
     scope_id: ScopeId,
-    lookup: HashMap<&'m ast::name::Name, SymbolId>,
+    lookup: HashMap<&'m str, SymbolId>,
+    /// The iteration variables of the comprehensions we are currently in.
+    iteration_vars: Vec<&'m str>,
+    /// The number of comprehensions we are currently in.
+    comprehension_depth: usize,
+    /// Whether we are in the iterable of a comprehension.
+    in_iterable: bool,
+    /// The alternative branches we are currently in, as the statement choosing between the
+    /// branches and the index of the branch, outermost first.
+    branches: Vec<(ast::NodeIndex, usize)>,
+    /// The branches the first binding of each symbol is in, if it is in any.
+    first_branches: HashMap<SymbolId, Vec<(ast::NodeIndex, usize)>>,
 }
 
 impl<'m, 's> ScopeLoopkupBuilder<'m, 's> {
@@ -34,11 +70,19 @@ impl<'m, 's> ScopeLoopkupBuilder<'m, 's> {
             errors,
             scope_id,
             lookup,
+            iteration_vars: Vec::new(),
+            comprehension_depth: 0,
+            in_iterable: false,
+            branches: Vec::new(),
+            first_branches: HashMap::new(),
         }
     }
 
     pub(super) fn build(self) -> ScopeLookup<'m> {
-        ScopeLookup(self.lookup)
+        ScopeLookup {
+            scope_id: self.scope_id,
+            symbols: self.lookup,
+        }
     }
 
     fn make_symbol(
@@ -55,36 +99,54 @@ impl<'m, 's> ScopeLoopkupBuilder<'m, 's> {
         kind: SymbolKind,
         name: &'m (impl HasId + HasNodeIndex + Ranged),
         decl_defn: DeclOrDefn<impl HasNodeIndex>,
-    ) {
-        use std::collections::hash_map::Entry;
+    ) -> SymbolId {
         let symbol = Symbol::make(kind, self.scope_id, name, decl_defn);
-        match self.lookup.entry(name.id()) {
+        self.insert_symbol(name.id().as_str(), symbol)
+    }
+
+    fn insert_symbol(&mut self, name: &'m str, symbol: Symbol) -> SymbolId {
+        use std::collections::hash_map::Entry;
+        match self.lookup.entry(name) {
             Entry::Vacant(entry) => {
                 let id = self.symbols.insert(symbol);
-                self.scopes.add_symbol(self.scope_id, id);
+                self.scopes.add_symbol(self.scope_id, name, id);
                 entry.insert(id);
+                if !self.branches.is_empty() {
+                    self.first_branches.insert(id, self.branches.clone());
+                }
+                id
             }
             Entry::Occupied(entry) => {
                 let id = *entry.get();
                 let previous = self.symbols.get(id);
                 let (merged, conflict) = previous.merge(&symbol);
-                if conflict {
-                    let error = TypeError::new(
-                        symbol.name_range,
-                        format!(
-                            "{} definition conflicts with earlier {} definition at {:?}",
-                            symbol.kind, previous.kind, previous.name_range,
-                        ),
-                    );
-                    self.errors.add(error);
+                // Only one of the alternative definitions in, e.g., `if X: def f(): ...` and
+                // `else: def f(): ...` is executed.
+                let alternative = self
+                    .first_branches
+                    .get(&id)
+                    .is_some_and(|branches| are_alternatives(branches, &self.branches));
+                let conflict = conflict
+                    .filter(|conflict| !(alternative && *conflict == Conflict::Redefinition));
+                if let Some(conflict) = conflict {
+                    let message = conflict.message(name, previous, &symbol);
+                    self.errors.add(TypeError::new(symbol.name_range, message));
                 }
                 if let Some(merged) = merged {
                     *self.symbols.get_mut(id) = merged;
                 }
+                id
             }
         }
     }
 
+    fn add_import(&mut self, name: &'m str, symbol: Symbol, import: Import) {
+        let id = self.insert_symbol(name, symbol);
+        if self.symbols.get(id).kind == SymbolKind::Import {
+            self.symbols.insert_import(id, import);
+        }
+    }
+
     pub(super) fn add_stmt(&mut self, stmt: &'m ast::Stmt) {
         use DeclOrDefn::*;
         match stmt {
@@ -99,47 +161,196 @@ impl<'m, 's> ScopeLoopkupBuilder<'m, 's> {
                     unreachable!("The grammar only allows `type A = ...` and `type A[...] = ...`.");
                 }
             },
-            ast::Stmt::Assign(assign) => match &assign.targets[..] {
-                [target] => match target {
-                    ast::Expr::Name(name) => {
-                        self.add_symbol(SymbolKind::Variable, name, Defn(assign));
-                    }
-                    _ => self.errors.add(TypeError::new(
-                        target.range(),
-                        "only name targets are supported",
-                    )),
-                },
-
-                _ => {
-                    self.errors.add(TypeError::new(
-                        assign.range,
-                        "only single target assignments are support",
-                    ));
+            ast::Stmt::Assign(assign) => {
+                // All targets of a chained assignment like `a = b = value` share the statement as
+                // their definition since they are all assigned the same value.
+                for target in &assign.targets {
+                    self.add_target(target, assign);
                 }
-            },
+            }
+            ast::Stmt::AugAssign(assign) => self.add_target(&assign.target, assign),
             ast::Stmt::AnnAssign(assign) => match &*assign.target {
                 ast::Expr::Name(name) => {
                     if assign.value.is_some() {
-                        self.add_symbol(SymbolKind::Variable, name, DeclAndDefn(assign))
+                        self.add_symbol(SymbolKind::Variable, name, DeclAndDefn(assign));
                     } else {
-                        self.add_symbol(SymbolKind::Variable, name, Decl(assign))
+                        self.add_symbol(SymbolKind::Variable, name, Decl(assign));
                     }
                 }
+                ast::Expr::Attribute(_) | ast::Expr::Subscript(_) => {}
                 _ => self.errors.add(TypeError::new(
                     assign.target.range(),
-                    "only name targets are supported",
+                    "only single target (not tuple) can be annotated",
                 )),
             },
             ast::Stmt::FunctionDef(func_def) => {
-                self.add_symbol(SymbolKind::Function, &func_def.name, Decl(func_def));
+                let id = self.add_symbol(SymbolKind::Function, &func_def.name, Decl(func_def));
+                if self.symbols.get(id).kind == SymbolKind::Function
+                    && func_def.returns.as_deref().is_some_and(is_no_return)
+                {
+                    self.symbols.insert_no_return(id);
+                }
+            }
+            ast::Stmt::Delete(delete) => {
+                for target in &delete.targets {
+                    self.add_deleted(target);
+                }
+            }
+            ast::Stmt::For(for_stmt) => self.add_target(&for_stmt.target, for_stmt),
+            ast::Stmt::With(with) => {
+                for item in &with.items {
+                    if let Some(target) = &item.optional_vars {
+                        self.add_target(target, item);
+                    }
+                }
+            }
+            ast::Stmt::Try(try_stmt) => {
+                for handler in &try_stmt.handlers {
+                    let ast::ExceptHandler::ExceptHandler(handler) = handler;
+                    if let Some(name) = &handler.name {
+                        self.add_symbol(SymbolKind::Variable, name, Defn(handler));
+                    }
+                }
+            }
+            ast::Stmt::Import(import) => {
+                for alias in &import.names {
+                    let details = Import {
+                        module: Some(alias.name.id.clone()),
+                        member: None,
+                        asname: alias.asname.as_ref().map(|asname| asname.id.clone()),
+                        level: 0,
+                    };
+                    match &alias.asname {
+                        Some(asname) => {
+                            let symbol = Symbol::make(
+                                SymbolKind::Import,
+                                self.scope_id,
+                                asname,
+                                Defn(alias),
+                            );
+                            self.add_import(asname.id.as_str(), symbol, details);
+                        }
+                        None => {
+                            // `import a.b.c` binds the top-level package `a`.
+                            let id = alias.name.id.as_str();
+                            let package = id.split_once('.').map_or(id, |(package, _)| package);
+                            let symbol = Symbol::make(
+                                SymbolKind::Import,
+                                self.scope_id,
+                                &alias.name,
+                                Defn(alias),
+                            );
+                            let name_range =
+                                TextRange::at(alias.name.start(), TextSize::of(package));
+                            self.add_import(package, Symbol { name_range, ..symbol }, details);
+                        }
+                    }
+                }
+            }
+            ast::Stmt::ImportFrom(import) => {
+                for alias in &import.names {
+                    let name = alias.asname.as_ref().unwrap_or(&alias.name);
+                    // Names bound by `from a import *` are not known statically. We only remember
+                    // that the scope might bind any name.
+                    if name.id.as_str() == "*" {
+                        self.scopes.add_star_import(self.scope_id);
+                        continue;
+                    }
+                    let details = Import {
+                        module: import.module.as_ref().map(|module| module.id.clone()),
+                        member: Some(alias.name.id.clone()),
+                        asname: alias.asname.as_ref().map(|asname| asname.id.clone()),
+                        level: import.level,
+                    };
+                    let symbol = Symbol::make(SymbolKind::Import, self.scope_id, name, Defn(alias));
+                    self.add_import(name.id.as_str(), symbol, details);
+                }
+            }
+            ast::Stmt::Global(global) => {
+                // A `global` declaration at module level has no effect.
+                if self.scopes.get(self.scope_id).kind() != ScopeKind::Module {
+                    for name in &global.names {
+                        self.add_symbol(SymbolKind::Global, name, Decl(global));
+                    }
+                }
             }
             ast::Stmt::Nonlocal(nonlocal) => {
-                for name in &nonlocal.names {
-                    self.add_symbol(SymbolKind::Nonlocal, name, Decl(nonlocal));
+                if self.scopes.get(self.scope_id).kind() == ScopeKind::Module {
+                    self.errors.add(TypeError::new(
+                        nonlocal.range,
+                        "nonlocal declaration not allowed at module level",
+                    ));
+                } else {
+                    for name in &nonlocal.names {
+                        self.add_symbol(SymbolKind::Nonlocal, name, Decl(nonlocal));
+                    }
                 }
             }
             _ => {}
         }
+        // Named expressions, patterns and nested statements can bind names anywhere in the
+        // statement. The bodies of functions and classes are new scopes though.
+        match stmt {
+            ast::Stmt::FunctionDef(func_def) => {
+                for decorator in &func_def.decorator_list {
+                    self.visit_decorator(decorator);
+                }
+                self.visit_parameters(&func_def.parameters);
+                if let Some(returns) = &func_def.returns {
+                    self.visit_annotation(returns);
+                }
+            }
+            ast::Stmt::ClassDef(class_def) => {
+                for decorator in &class_def.decorator_list {
+                    self.visit_decorator(decorator);
+                }
+                if let Some(arguments) = &class_def.arguments {
+                    self.visit_arguments(arguments);
+                }
+            }
+            ast::Stmt::If(if_stmt) => {
+                self.visit_expr(&if_stmt.test);
+                self.add_branch(stmt, 0, &if_stmt.body);
+                for (index, clause) in if_stmt.elif_else_clauses.iter().enumerate() {
+                    if let Some(test) = &clause.test {
+                        self.visit_expr(test);
+                    }
+                    self.add_branch(stmt, index + 1, &clause.body);
+                }
+            }
+            ast::Stmt::Match(match_stmt) => {
+                self.visit_expr(&match_stmt.subject);
+                for (index, case) in match_stmt.cases.iter().enumerate() {
+                    self.branches.push((stmt.node_index().load(), index));
+                    self.visit_match_case(case);
+                    self.branches.pop();
+                }
+            }
+            // The handlers are alternatives to the body and the `else` clause, e.g., in
+            // `try: from a import f` and `except ImportError: def f(): ...`.
+            ast::Stmt::Try(try_stmt) => {
+                self.branches.push((stmt.node_index().load(), 0));
+                self.add_block(&try_stmt.body);
+                self.add_block(&try_stmt.orelse);
+                self.branches.pop();
+                for (index, handler) in try_stmt.handlers.iter().enumerate() {
+                    let ast::ExceptHandler::ExceptHandler(handler) = handler;
+                    if let Some(type_) = &handler.type_ {
+                        self.visit_expr(type_);
+                    }
+                    self.add_branch(stmt, index + 1, &handler.body);
+                }
+                self.add_block(&try_stmt.finalbody);
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    /// Collects the bindings in the branch with the index `index` of the statement `stmt`.
+    fn add_branch(&mut self, stmt: &'m ast::Stmt, index: usize, body: &'m Vec<ast::Stmt>) {
+        self.branches.push((stmt.node_index().load(), index));
+        self.add_block(body);
+        self.branches.pop();
     }
 
     pub(super) fn add_block(&mut self, stmts: &'m Vec<ast::Stmt>) {
@@ -147,4 +358,281 @@ impl<'m, 's> ScopeLoopkupBuilder<'m, 's> {
             self.add_stmt(stmt);
         }
     }
+
+    pub(super) fn add_parameters(&mut self, parameters: &'m ast::Parameters) {
+        use ParameterKind::*;
+        let ast::Parameters {
+            posonlyargs,
+            args,
+            vararg,
+            kwonlyargs,
+            kwarg,
+            ..
+        } = parameters;
+        // We bind the parameters in source order to report duplicates at the later occurrence.
+        for parameter in posonlyargs {
+            self.add_parameter(
+                PositionalOnly,
+                parameter,
+                &parameter.parameter,
+                &parameter.default,
+            );
+        }
+        for parameter in args {
+            self.add_parameter(
+                PositionalOrKeyword,
+                parameter,
+                &parameter.parameter,
+                &parameter.default,
+            );
+        }
+        if let Some(parameter) = vararg.as_deref() {
+            self.add_parameter(VarPositional, parameter, parameter, &None);
+        }
+        for parameter in kwonlyargs {
+            self.add_parameter(
+                KeywordOnly,
+                parameter,
+                &parameter.parameter,
+                &parameter.default,
+            );
+        }
+        if let Some(parameter) = kwarg.as_deref() {
+            self.add_parameter(VarKeyword, parameter, parameter, &None);
+        }
+    }
+
+    /// Binds a single parameter. `node` is either `parameter` itself or the
+    /// `ast::ParameterWithDefault` containing it.
+    fn add_parameter(
+        &mut self,
+        kind: ParameterKind,
+        node: impl HasNodeIndex,
+        parameter: &'m ast::Parameter,
+        default: &Option<Box<ast::Expr>>,
+    ) {
+        let decl_defn = if parameter.annotation.is_some() {
+            DeclOrDefn::DeclAndDefn(node)
+        } else {
+            DeclOrDefn::Defn(node)
+        };
+        let id = self.add_symbol(SymbolKind::Parameter, &parameter.name, decl_defn);
+        if self.symbols.get(id).kind == SymbolKind::Parameter {
+            let details = Parameter {
+                kind,
+                annotation: parameter
+                    .annotation
+                    .as_deref()
+                    .map(|annotation| annotation.node_index().load()),
+                default: default
+                    .as_deref()
+                    .map(|default| default.node_index().load()),
+            };
+            self.symbols.insert_parameter(id, details);
+        }
+    }
+
+    /// Binds the type parameters of a generic class, function or type alias. All names must be
+    /// distinct.
+    pub(super) fn add_type_params(&mut self, type_params: &'m ast::TypeParams) {
+        for type_param in &type_params.type_params {
+            let (kind, name) = match type_param {
+                ast::TypeParam::TypeVar(ast::TypeParamTypeVar { name, .. }) => {
+                    (SymbolKind::TypeVar, name)
+                }
+                ast::TypeParam::ParamSpec(ast::TypeParamParamSpec { name, .. }) => {
+                    (SymbolKind::ParamSpec, name)
+                }
+                ast::TypeParam::TypeVarTuple(ast::TypeParamTypeVarTuple { name, .. }) => {
+                    (SymbolKind::TypeVarTuple, name)
+                }
+            };
+            if self.lookup.contains_key(name.id.as_str()) {
+                self.errors.add(TypeError::new(
+                    name.range,
+                    format!("duplicate type parameter '{}'", name.id),
+                ));
+                continue;
+            }
+            self.add_symbol(kind, name, DeclOrDefn::DeclAndDefn(type_param));
+        }
+    }
+
+    pub(super) fn add_generators(&mut self, generators: &'m [ast::Comprehension]) {
+        for generator in generators {
+            self.add_target(&generator.target, generator);
+        }
+    }
+
+    /// Binds all names in the assignment target `target` as variables defined by `defn`. The
+    /// target can be arbitrarily nested tuple, list and starred patterns. Attribute and subscript
+    /// targets do not bind any names.
+    pub(super) fn add_target(&mut self, target: &'m ast::Expr, defn: impl HasNodeIndex + Copy) {
+        match target {
+            ast::Expr::Name(name) => {
+                self.add_symbol(SymbolKind::Variable, name, DeclOrDefn::Defn(defn));
+            }
+            ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+            | ast::Expr::List(ast::ExprList { elts, .. }) => {
+                for elt in elts {
+                    self.add_target(elt, defn);
+                }
+            }
+            ast::Expr::Starred(starred) => self.add_target(&starred.value, defn),
+            ast::Expr::Attribute(_) | ast::Expr::Subscript(_) => {}
+            _ => self.errors.add(TypeError::new(
+                target.range(),
+                "cannot assign to expression",
+            )),
+        }
+    }
+
+    /// Walks a comprehension nested in the current scope to find assignment expressions. Their
+    /// targets are bound in the closest enclosing scope that is not a comprehension (PEP 572).
+    fn add_comprehension(&mut self, elts: &[&'m ast::Expr], generators: &'m [ast::Comprehension]) {
+        let iteration_vars = self.iteration_vars.len();
+        for generator in generators {
+            collect_names(&generator.target, &mut self.iteration_vars);
+        }
+        self.comprehension_depth += 1;
+        for generator in generators {
+            let in_iterable = std::mem::replace(&mut self.in_iterable, true);
+            self.visit_expr(&generator.iter);
+            self.in_iterable = in_iterable;
+            for condition in &generator.ifs {
+                self.visit_expr(condition);
+            }
+        }
+        for elt in elts {
+            self.visit_expr(elt);
+        }
+        self.comprehension_depth -= 1;
+        self.iteration_vars.truncate(iteration_vars);
+    }
+
+    /// Binds the target of an assignment expression inside a comprehension in the current scope,
+    /// unless CPython rejects the assignment expression.
+    fn add_comprehension_named(&mut self, named: &'m ast::ExprNamed) {
+        let message = if let ast::Expr::Name(name) = &*named.target
+            && self.iteration_vars.contains(&name.id.as_str())
+        {
+            format!(
+                "assignment expression cannot rebind comprehension iteration variable '{}'",
+                name.id
+            )
+        } else if self.in_iterable {
+            "assignment expression cannot be used in a comprehension iterable expression"
+                .to_string()
+        } else if self.scopes.get(self.scope_id).kind() == ScopeKind::Class {
+            "assignment expression within a comprehension cannot be used in a class body"
+                .to_string()
+        } else {
+            self.add_target(&named.target, named);
+            return;
+        };
+        self.errors.add(TypeError::new(named.range, message));
+    }
+
+    /// Binds all names in the `del` target `target`. Deleting a name makes it local to the scope
+    /// but does not define it.
+    fn add_deleted(&mut self, target: &'m ast::Expr) {
+        match target {
+            ast::Expr::Name(name) => {
+                let symbol = Symbol::make(
+                    SymbolKind::Variable,
+                    self.scope_id,
+                    name,
+                    DeclOrDefn::Defn(name),
+                );
+                let symbol = Symbol { defn: no_node_index(), ..symbol };
+                self.insert_symbol(name.id.as_str(), symbol);
+            }
+            ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+            | ast::Expr::List(ast::ExprList { elts, .. }) => {
+                for elt in elts {
+                    self.add_deleted(elt);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether the branches `a` and `b` are alternatives, i.e., different branches of the same
+/// statement. Both lists of branches are ordered outermost first.
+fn are_alternatives(a: &[(ast::NodeIndex, usize)], b: &[(ast::NodeIndex, usize)]) -> bool {
+    a.iter()
+        .zip(b)
+        .find(|(a, b)| a != b)
+        .is_some_and(|((a_stmt, _), (b_stmt, _))| a_stmt == b_stmt)
+}
+
+/// Whether the return annotation `returns` says that a function never returns.
+fn is_no_return(returns: &ast::Expr) -> bool {
+    let name = match returns {
+        ast::Expr::Name(name) => name.id.as_str(),
+        // E.g. `typing.NoReturn`.
+        ast::Expr::Attribute(attribute) => attribute.attr.as_str(),
+        _ => return false,
+    };
+    matches!(name, "NoReturn" | "Never")
+}
+
+/// Collects the names bound by the assignment target `target`.
+fn collect_names<'m>(target: &'m ast::Expr, names: &mut Vec<&'m str>) {
+    match target {
+        ast::Expr::Name(name) => names.push(name.id.as_str()),
+        ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+        | ast::Expr::List(ast::ExprList { elts, .. }) => {
+            for elt in elts {
+                collect_names(elt, names);
+            }
+        }
+        ast::Expr::Starred(starred) => collect_names(&starred.value, names),
+        _ => {}
+    }
+}
+
+impl<'m> SourceOrderVisitor<'m> for ScopeLoopkupBuilder<'m, '_> {
+    fn visit_stmt(&mut self, stmt: &'m ast::Stmt) {
+        self.add_stmt(stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &'m ast::Expr) {
+        match expr {
+            ast::Expr::Named(named) => {
+                if self.comprehension_depth > 0 {
+                    self.add_comprehension_named(named);
+                } else {
+                    self.add_target(&named.target, named);
+                }
+                self.visit_expr(&named.value);
+            }
+            // Lambdas have their own scope.
+            ast::Expr::Lambda(_) => {}
+            // Comprehensions have their own scope too but assignment expressions in them bind
+            // names in the current scope.
+            ast::Expr::ListComp(ast::ExprListComp { elt, generators, .. })
+            | ast::Expr::SetComp(ast::ExprSetComp { elt, generators, .. })
+            | ast::Expr::Generator(ast::ExprGenerator { elt, generators, .. }) => {
+                self.add_comprehension(&[&**elt], generators);
+            }
+            ast::Expr::DictComp(ast::ExprDictComp { key, value, generators, .. }) => {
+                self.add_comprehension(&[&**key, &**value], generators);
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+
+    fn visit_pattern(&mut self, pattern: &'m ast::Pattern) {
+        match pattern {
+            ast::Pattern::MatchAs(ast::PatternMatchAs { name: Some(name), .. })
+            | ast::Pattern::MatchStar(ast::PatternMatchStar { name: Some(name), .. })
+            | ast::Pattern::MatchMapping(ast::PatternMatchMapping { rest: Some(name), .. }) => {
+                self.add_symbol(SymbolKind::Variable, name, DeclOrDefn::Defn(pattern));
+            }
+            _ => {}
+        }
+        walk_pattern(self, pattern);
+    }
 }
@@ -0,0 +1,1237 @@
+use ast::visitor::source_order::{SourceOrderVisitor, walk_expr};
+
+use super::*;
+use crate::indexed::IndexedModule;
+
+fn line(source: &str, range: text_size::TextRange) -> usize {
+    source[..usize::from(range.start())].matches('\n').count() + 1
+}
+
+struct Loads<'a>(Vec<&'a ast::ExprName>);
+
+impl<'a> SourceOrderVisitor<'a> for Loads<'a> {
+    fn visit_expr(&mut self, expr: &'a ast::Expr) {
+        if let ast::Expr::Name(name) = expr
+            && matches!(name.ctx, ast::ExprContext::Load)
+        {
+            self.0.push(name);
+        }
+        walk_expr(self, expr);
+    }
+}
+
+fn resolve(source: &str) -> (Resolution, Vec<String>) {
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let outcome = Resolver::new(module.syntax()).run();
+    let errors = outcome
+        .errors
+        .into_iter()
+        .map(|error| error.message)
+        .collect();
+    (outcome.value, errors)
+}
+
+/// Describes the binding of every name load in `source` as `name@line -> kind@line`, where the
+/// second line is the one of the symbol the name resolves to.
+fn bindings(source: &str) -> Vec<String> {
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let resolution = Resolver::new(module.syntax()).run().value;
+    let mut loads = Loads(Vec::new());
+    loads.visit_body(&module.syntax().body);
+    loads
+        .0
+        .iter()
+        .map(|name| {
+            let target = match resolution.nodes.get(&name.node_index.load()) {
+                Some(id) => {
+                    let symbol = resolution.symbols.get(*id);
+                    match symbol.kind {
+                        SymbolKind::Builtin => symbol.kind.to_string(),
+                        kind => format!("{kind}@{}", line(source, symbol.name_range)),
+                    }
+                }
+                None => "?".to_string(),
+            };
+            format!("{}@{} -> {target}", name.id, line(source, name.range))
+        })
+        .collect()
+}
+
+/// Renders the scope tree below the module scope, one scope per line, indented by depth and with
+/// the sorted names of the symbols of the scope.
+fn scope_tree(source: &str) -> Vec<String> {
+    fn render(scopes: &ScopeTable, id: ScopeId, depth: usize, lines: &mut Vec<String>) {
+        let scope = scopes.get(id);
+        let mut names: Vec<_> = scope.symbols().map(|(name, _)| name).collect();
+        names.sort_unstable();
+        let line = format!(
+            "{}{:?} {}",
+            "  ".repeat(depth),
+            scope.kind(),
+            names.join(" ")
+        );
+        lines.push(line.trim_end().to_string());
+        for child in scope.children() {
+            assert_eq!(scopes.get(*child).parent(), Some(id));
+            render(scopes, *child, depth + 1, lines);
+        }
+    }
+
+    let (resolution, _) = resolve(source);
+    let scopes = resolution.scopes();
+    assert_eq!(scopes.root().parent(), Some(scopes.builtins_id()));
+    let mut lines = Vec::new();
+    render(scopes, scopes.root_id(), 0, &mut lines);
+    lines
+}
+
+#[test]
+fn scopes_form_a_tree() {
+    let source = "\
+class A:
+    x = 1
+    def f(self, y):
+        g = lambda z: [w for w in z]
+        return g
+def h(): pass
+s = {k for k in range(3)}
+";
+    assert_eq!(
+        scope_tree(source),
+        &[
+            "Module A h s",
+            "  Class f x",
+            "    Function g self y",
+            "      Lambda z",
+            "        Comprehension w",
+            "  Function",
+            "  Comprehension k",
+        ]
+    );
+}
+
+#[test]
+fn loads_resolve_through_legb_chain() {
+    let source = "\
+x = 1
+def f(y):
+    def g():
+        return x + y + len
+    return g
+print(f)
+";
+    assert_eq!(
+        bindings(source),
+        &[
+            "x@4 -> variable@1",
+            "y@4 -> parameter@2",
+            "len@4 -> builtin",
+            "g@5 -> function@3",
+            "print@6 -> builtin",
+            "f@6 -> function@2",
+        ]
+    );
+}
+
+#[test]
+fn class_scope_is_invisible_to_methods() {
+    let source = "\
+x = 1
+class C:
+    x = 2
+    y = x
+    def m(self):
+        return x
+";
+    assert_eq!(
+        bindings(source),
+        &["x@4 -> variable@3", "x@6 -> variable@1"]
+    );
+}
+
+#[test]
+fn class_scope_pitfalls() {
+    let source = "\
+x = 0
+class A:
+    x = 1
+    y = [x for _ in range(3)]
+    z = [i for i in range(x) if x]
+    d = {k: x for k in range(x)}
+    f = lambda: x
+    def m(self):
+        return x, A
+    class B:
+        w = x
+";
+    assert_eq!(
+        bindings(source),
+        &[
+            "x@4 -> variable@1",
+            "range@4 -> builtin",
+            "i@5 -> variable@5",
+            "range@5 -> builtin",
+            "x@5 -> variable@3",
+            "x@5 -> variable@1",
+            "k@6 -> variable@6",
+            "x@6 -> variable@1",
+            "range@6 -> builtin",
+            "x@6 -> variable@3",
+            "x@7 -> variable@1",
+            "x@9 -> variable@1",
+            "A@9 -> class@2",
+            "x@11 -> variable@1",
+        ]
+    );
+}
+
+#[test]
+fn walrus_in_comprehension_binds_in_enclosing_scope() {
+    let source = "\
+def f(xs):
+    ys = [y for x in xs if (y := x)]
+    gen = (w for x in xs if (w := x))
+    return y, [[(v := x) for _ in xs] for x in xs], v
+g = lambda: [(u := 1) for _ in ()] and u
+";
+    assert_eq!(
+        bindings(source),
+        &[
+            "y@2 -> variable@2",
+            "xs@2 -> parameter@1",
+            "x@2 -> variable@2",
+            "w@3 -> variable@3",
+            "xs@3 -> parameter@1",
+            "x@3 -> variable@3",
+            "y@4 -> variable@2",
+            "x@4 -> variable@4",
+            "xs@4 -> parameter@1",
+            "xs@4 -> parameter@1",
+            "v@4 -> variable@4",
+            "u@5 -> variable@5",
+        ]
+    );
+    // The comprehensions might not iterate at all.
+    assert_eq!(
+        resolve(source).1,
+        &[
+            "name 'y' is possibly unbound",
+            "name 'v' is possibly unbound",
+        ]
+    );
+}
+
+#[test]
+fn invalid_walrus_in_comprehension() {
+    let source = "\
+[(x := 1) for x in range(3)]
+[[(x := 1) for _ in range(3)] for x in range(3)]
+[x for x in (y := range(3))]
+[x for x in range(3) for _ in [(z := x)] if (z := 2)]
+class C:
+    ys = [(y := x) for x in range(3)]
+";
+    let (_, errors) = resolve(source);
+    assert_eq!(
+        errors,
+        &[
+            "assignment expression cannot rebind comprehension iteration variable 'x'",
+            "assignment expression cannot rebind comprehension iteration variable 'x'",
+            "assignment expression cannot be used in a comprehension iterable expression",
+            "assignment expression cannot be used in a comprehension iterable expression",
+            "assignment expression within a comprehension cannot be used in a class body",
+        ]
+    );
+}
+
+#[test]
+fn type_parameters_live_in_annotation_scopes() {
+    let source = "\
+class A:
+    X = int
+    type L[T] = dict[T, X]
+    class B[T](list[T]):
+        def m[S](self, x: T, y: S) -> S:
+            return X
+def f[T, *Ts, **P](x: T, *args: *Ts) -> T:
+    return x
+";
+    assert_eq!(
+        bindings(source),
+        &[
+            "int@2 -> builtin",
+            "dict@3 -> builtin",
+            "T@3 -> type variable@3",
+            "X@3 -> variable@2",
+            "list@4 -> builtin",
+            "T@4 -> type variable@4",
+            "T@5 -> type variable@4",
+            "S@5 -> type variable@5",
+            "S@5 -> type variable@5",
+            "X@6 -> ?",
+            "T@7 -> type variable@7",
+            "Ts@7 -> type variable tuple@7",
+            "T@7 -> type variable@7",
+            "x@8 -> parameter@7",
+        ]
+    );
+}
+
+#[test]
+fn duplicate_type_parameters() {
+    let source = "\
+def f[T, T](): pass
+class C[T, *T, **T]: pass
+type A[T, U] = dict[T, U]
+";
+    let (_, errors) = resolve(source);
+    assert_eq!(
+        errors,
+        &[
+            "duplicate type parameter 'T'",
+            "duplicate type parameter 'T'",
+            "duplicate type parameter 'T'",
+        ]
+    );
+}
+
+#[test]
+fn signatures_are_evaluated_in_the_enclosing_scope() {
+    let source = "\
+x = 1
+def deco(f): return f
+class A:
+    x = 2
+    @deco
+    def m(self, y: x = x) -> x:
+        return x
+    class B(x, metaclass=x): pass
+    g = lambda z=x: z
+def h[T](a: T = T) -> T: pass
+";
+    assert_eq!(
+        bindings(source),
+        &[
+            "f@2 -> parameter@2",
+            "deco@5 -> function@2",
+            "x@6 -> variable@4",
+            "x@6 -> variable@4",
+            "x@6 -> variable@4",
+            "x@7 -> variable@1",
+            "x@8 -> variable@4",
+            "x@8 -> variable@4",
+            "x@9 -> variable@4",
+            "z@9 -> parameter@9",
+            "T@10 -> type variable@10",
+            "T@10 -> ?",
+            "T@10 -> type variable@10",
+        ]
+    );
+}
+
+#[test]
+fn unresolved_loads_are_not_recorded() {
+    let (resolution, errors) = resolve("print(undefined)\n");
+    assert_eq!(resolution.nodes.len(), 1);
+    assert_eq!(errors, &["name 'undefined' is not defined"]);
+}
+
+#[test]
+fn undefined_names_suggest_close_visible_names() {
+    let source = "\
+import collections
+def f(value):
+    return valeu + lenn(value)
+pirnt(f(1))
+collection.deque()
+xyz
+";
+    let (_, errors) = resolve(source);
+    assert_eq!(
+        errors,
+        &[
+            "name 'valeu' is not defined. Did you mean: 'value'?",
+            "name 'lenn' is not defined. Did you mean: 'len'?",
+            "name 'pirnt' is not defined. Did you mean: 'print'?",
+            "name 'collection' is not defined. Did you mean: 'collections'?",
+            "name 'xyz' is not defined",
+        ]
+    );
+}
+
+#[test]
+fn star_imports_suppress_undefined_names() {
+    let source = "\
+from os.path import *
+def f(p):
+    return join(dirname(p), basename(p))
+print(sep)
+";
+    let (resolution, errors) = resolve(source);
+    assert!(errors.is_empty());
+    assert!(resolution.scopes().root().has_star_import());
+    assert!(!resolve("print(sep)\n").0.scopes().root().has_star_import());
+}
+
+#[test]
+fn global_declarations_bind_module_level_names() {
+    let source = "\
+def g():
+    return x
+def f():
+    global x
+    x = 1
+def h():
+    global print
+    return x, print
+";
+    assert_eq!(
+        bindings(source),
+        &[
+            "x@2 -> variable@4",
+            "x@8 -> variable@4",
+            "print@8 -> builtin"
+        ]
+    );
+}
+
+#[test]
+fn global_declaration_conflicts() {
+    let source = "\
+def f(a):
+    b = 1
+    global a, b
+    global c
+    c: int = 1
+";
+    let (_, errors) = resolve(source);
+    assert_eq!(
+        errors,
+        &[
+            "name 'a' is parameter and global",
+            "name 'b' is assigned to before global declaration",
+            "annotated name 'c' can't be global",
+        ]
+    );
+}
+
+#[test]
+fn nonlocal_declarations_link_to_enclosing_function_scopes() {
+    let source = "\
+def f():
+    x = 1
+    class C:
+        x = 2
+        def g():
+            nonlocal x
+            x = 3
+            return x
+";
+    assert_eq!(bindings(source), &["x@8 -> variable@2"]);
+}
+
+#[test]
+fn invalid_nonlocal_declarations() {
+    let source = "\
+nonlocal a
+def f(b):
+    global c
+    def g(d):
+        nonlocal b, c, d, e
+";
+    let (_, errors) = resolve(source);
+    assert_eq!(
+        errors,
+        &[
+            "nonlocal declaration not allowed at module level",
+            "name 'd' is parameter and nonlocal",
+            "no binding for nonlocal 'c' found",
+            "no binding for nonlocal 'e' found",
+        ]
+    );
+}
+
+#[test]
+fn destructuring_targets_bind_every_name() {
+    let source = "\
+a, (b, [c, *d]) = range(4)
+obj.attr, items[0] = a, b
+print(c, d)
+";
+    assert_eq!(
+        bindings(source),
+        &[
+            "range@1 -> builtin",
+            "obj@2 -> ?",
+            "items@2 -> ?",
+            "a@2 -> variable@1",
+            "b@2 -> variable@1",
+            "print@3 -> builtin",
+            "c@3 -> variable@1",
+            "d@3 -> variable@1",
+        ]
+    );
+    assert_eq!(
+        resolve(source).1,
+        &[
+            "name 'obj' is not defined",
+            "name 'items' is not defined. Did you mean: 'iter'?",
+        ]
+    );
+}
+
+#[test]
+fn chained_assignments_share_their_definition() {
+    let source = "\
+a = b, c = d = 1, 2
+print(a, b, c, d)
+";
+    let (resolution, errors) = resolve(source);
+    assert!(errors.is_empty());
+    let variables: Vec<_> = resolution
+        .nodes
+        .values()
+        .map(|id| resolution.symbols.get(*id))
+        .filter(|symbol| symbol.kind == SymbolKind::Variable)
+        .collect();
+    assert_eq!(variables.len(), 4);
+    assert!(
+        variables
+            .iter()
+            .all(|symbol| symbol.defn == variables[0].defn)
+    );
+}
+
+#[test]
+fn all_binding_constructs_bind_names() {
+    let source = "\
+import os.path, collections as c
+from typing import Any as A, List
+for i, j in x: pass
+with open(f) as fh: pass
+try: pass
+except E as e: pass
+if (n := 10): pass
+match v:
+    case [p, *ps]: pass
+    case {'k': q, **kw}: pass
+    case Point(x=r) as pt: pass
+k += 1
+del d
+print(os, c, A, List, i, j, fh, e, n, p, ps, q, kw, r, pt, k, d)
+";
+    let bindings: Vec<_> = bindings(source)
+        .into_iter()
+        .filter(|binding| binding.contains("@14 "))
+        .collect();
+    assert_eq!(
+        bindings,
+        &[
+            "print@14 -> builtin",
+            "os@14 -> import@1",
+            "c@14 -> import@1",
+            "A@14 -> import@2",
+            "List@14 -> import@2",
+            "i@14 -> variable@3",
+            "j@14 -> variable@3",
+            "fh@14 -> variable@4",
+            "e@14 -> variable@6",
+            "n@14 -> variable@7",
+            "p@14 -> variable@9",
+            "ps@14 -> variable@9",
+            "q@14 -> variable@10",
+            "kw@14 -> variable@10",
+            "r@14 -> variable@11",
+            "pt@14 -> variable@11",
+            "k@14 -> variable@12",
+            "d@14 -> variable@13",
+        ]
+    );
+}
+
+#[test]
+fn bindings_in_nested_blocks_belong_to_enclosing_scope() {
+    let source = "\
+if c:
+    a = 1
+elif d:
+    def f(): pass
+else:
+    while True:
+        b = 1
+    else:
+        class C: pass
+try:
+    import g
+except E:
+    h = 1
+finally:
+    with w:
+        i = 1
+match m:
+    case _:
+        j = 1
+def k():
+    l = 1
+print(a, f, b, C, g, h, i, j, l)
+";
+    let bindings: Vec<_> = bindings(source)
+        .into_iter()
+        .filter(|binding| binding.contains("@22 "))
+        .collect();
+    assert_eq!(
+        bindings,
+        &[
+            "print@22 -> builtin",
+            "a@22 -> variable@2",
+            "f@22 -> function@4",
+            "b@22 -> variable@7",
+            "C@22 -> class@9",
+            "g@22 -> import@11",
+            "h@22 -> variable@13",
+            "i@22 -> variable@16",
+            "j@22 -> variable@19",
+            "l@22 -> ?",
+        ]
+    );
+}
+
+fn show(name: &Option<ast::name::Name>) -> &str {
+    name.as_ref().map_or("-", |name| name.as_str())
+}
+
+#[test]
+fn imports_record_their_details() {
+    let source = "\
+import a.b as c
+from ..d import e as f, g
+import h.i
+print(c, f, g, h)
+";
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let resolution = Resolver::new(module.syntax()).run().value;
+    let ast::Stmt::Expr(print) = &module.syntax().body[3] else {
+        panic!("expected an expression statement");
+    };
+    let ast::Expr::Call(call) = &*print.value else {
+        panic!("expected a call");
+    };
+    let imports: Vec<_> = call
+        .arguments
+        .args
+        .iter()
+        .map(|arg| {
+            let id = resolution.nodes[&arg.node_index().load()];
+            let import = resolution.symbols.import(id).unwrap();
+            format!(
+                "module={} member={} asname={} level={}",
+                show(&import.module),
+                show(&import.member),
+                show(&import.asname),
+                import.level,
+            )
+        })
+        .collect();
+    assert_eq!(
+        imports,
+        &[
+            "module=a.b member=- asname=c level=0",
+            "module=d member=e asname=f level=2",
+            "module=d member=g asname=- level=2",
+            "module=h.i member=- asname=- level=0",
+        ]
+    );
+}
+
+#[test]
+fn imports_merge_with_redefinitions() {
+    let source = "\
+import json
+try:
+    import json
+except ImportError:
+    json = None
+try:
+    from os import path
+except ImportError:
+    def path(): pass
+try:
+    from typing import Protocol
+except ImportError:
+    class Protocol: pass
+import sys
+sys: int = 1
+";
+    let (_, errors) = resolve(source);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("variable definition conflicts with earlier import"));
+}
+
+#[test]
+fn alternative_definitions_do_not_conflict() {
+    let source = "\
+import sys
+if sys.version_info >= (3, 11):
+    def f(): pass
+    x: int = 1
+else:
+    def f(): pass
+    x: str = ''
+try:
+    from a import g
+except ImportError:
+    def g(): pass
+try:
+    def h(): pass
+except ImportError:
+    from a import h
+match sys.platform:
+    case 'linux':
+        y: int = 1
+    case _:
+        if sys.maxsize > 2**32:
+            y: int = 1
+        else:
+            y: str = ''
+if sys.flags.debug:
+    z: int = 1
+    z: str = ''
+";
+    let (_, errors) = resolve(source);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("variable definition conflicts with earlier variable"));
+}
+
+#[test]
+fn parameters_merge_with_nested_definitions() {
+    let source = "\
+def f(x, y):
+    def x(): pass
+    class y: pass
+    return x, y
+";
+    assert!(resolve(source).1.is_empty());
+}
+
+#[test]
+fn functions_and_classes_can_be_rebound() {
+    let source = "\
+from typing import overload
+def deco(x): return x
+class C:
+    @property
+    def x(self): return 1
+    @x.setter
+    def x(self, value): pass
+    @overload
+    def f(self, a: int) -> int: ...
+    @overload
+    def f(self, a: str) -> str: ...
+    def f(self, a): return a
+def g(): pass
+g = deco(g)
+class D: pass
+D = deco(D)
+class D: pass
+def h(): pass
+h: int = 1
+";
+    let (_, errors) = resolve(source);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("variable definition conflicts with earlier function"));
+}
+
+#[test]
+fn parameters_record_their_details() {
+    let source = "\
+def f(a, /, b: int = 1, *args: int, c, d=2, **kwargs):
+    return a, b, args, c, d, kwargs
+";
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let resolution = Resolver::new(module.syntax()).run().value;
+    let mut loads = Loads(Vec::new());
+    loads.visit_body(&module.syntax().body);
+    let parameters: Vec<_> = loads
+        .0
+        .iter()
+        .filter_map(|name| {
+            let id = resolution.nodes.get(&name.node_index.load())?;
+            let parameter = resolution.symbols.parameter(*id)?;
+            Some(format!(
+                "{}: {:?} annotation={} default={}",
+                name.id,
+                parameter.kind,
+                parameter.annotation.is_some(),
+                parameter.default.is_some(),
+            ))
+        })
+        .collect();
+    assert_eq!(
+        parameters,
+        &[
+            "a: PositionalOnly annotation=false default=false",
+            "b: PositionalOrKeyword annotation=true default=true",
+            "args: VarPositional annotation=true default=false",
+            "c: KeywordOnly annotation=false default=false",
+            "d: KeywordOnly annotation=false default=true",
+            "kwargs: VarKeyword annotation=false default=false",
+        ]
+    );
+}
+
+#[test]
+fn invalid_parameters() {
+    let source = "\
+def f(a, b, /, c, *a, b=1, **c): pass
+g = lambda x, x: x
+def h(p, q):
+    global p
+    nonlocal q
+";
+    let (_, errors) = resolve(source);
+    assert_eq!(
+        errors,
+        &[
+            "duplicate argument 'a' in function definition",
+            "duplicate argument 'b' in function definition",
+            "duplicate argument 'c' in function definition",
+            "duplicate argument 'x' in function definition",
+            "name 'p' is parameter and global",
+            "name 'q' is parameter and nonlocal",
+        ]
+    );
+}
+
+#[test]
+fn uses_before_definition() {
+    let source = "\
+print(x)
+x = 1
+y = y + 1
+z += 1
+def f():
+    return a, g()
+a = [b for b in range(3)]
+@deco
+def deco(f): return f
+def g():
+    h()
+    def h(): pass
+    while k: pass
+    k = 1
+";
+    let (_, errors) = resolve(source);
+    assert_eq!(
+        errors,
+        &[
+            "name 'x' is used before it is defined",
+            "name 'y' is used before it is defined",
+            "name 'z' is used before it is defined",
+            "name 'deco' is used before it is defined",
+            "name 'h' is used before it is defined",
+            "name 'k' is used before it is defined",
+        ]
+    );
+}
+
+#[test]
+fn uses_before_definition_ignore_deferred_evaluation() {
+    let source = "\
+def f(x: A) -> A:
+    y: A = x
+    return y
+type B = list[A]
+class A: pass
+";
+    assert!(resolve(source).1.is_empty());
+}
+
+#[test]
+fn possibly_unbound_names() {
+    let source = "\
+import sys
+def f(c, xs):
+    if c:
+        a = 1
+    elif c > 1:
+        a = 2
+    else:
+        return
+    print(a)
+    if c:
+        b = 1
+    print(b)
+    try:
+        d = int(c)
+    except ValueError:
+        pass
+    print(d)
+    for x in xs:
+        e = x
+    print(e, x)
+    for x in xs:
+        if x:
+            print(g)
+        g = x
+    while True:
+        if c:
+            h = 1
+            break
+    print(h)
+    match c:
+        case 1:
+            i = 1
+        case _:
+            raise ValueError()
+    print(i)
+    if c:
+        j = 1
+    else:
+        sys.exit(1)
+    print(j)
+";
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let errors: Vec<_> = Resolver::new(module.syntax())
+        .run()
+        .errors
+        .into_iter()
+        .map(|error| {
+            let notes: Vec<_> = error
+                .notes
+                .iter()
+                .map(|note| format!("{}@{}", note.message, line(source, note.range)))
+                .collect();
+            format!(
+                "{}@{}: {}",
+                error.message,
+                line(source, error.range),
+                notes.join(", ")
+            )
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        &[
+            "name 'b' is possibly unbound@12: it is not bound on the path through here@10",
+            "name 'd' is possibly unbound@17: it is not bound on the path through here@15",
+            "name 'e' is possibly unbound@20: it is not bound on the path through here@18",
+            "name 'x' is possibly unbound@20: it is not bound on the path through here@18",
+            "name 'g' is possibly unbound@23: it is not bound on the path through here@21",
+        ]
+    );
+}
+
+#[test]
+fn definitely_unbound_names() {
+    let source = "\
+def f(c):
+    x = 1
+    del x
+    print(x)
+    if c:
+        y = 1
+        return
+    print(y)
+    z: int
+    print(z)
+    print(w)
+    w = 1
+";
+    let (_, errors) = resolve(source);
+    assert_eq!(
+        errors,
+        &[
+            "name 'x' is unbound",
+            "name 'y' is unbound",
+            "name 'z' is unbound",
+            "name 'w' is used before it is defined",
+        ]
+    );
+}
+
+#[test]
+fn deletions_do_not_conflict() {
+    let source = "\
+def f(): pass
+del f
+class C: pass
+del C
+type A = int
+del A
+def g():
+    del h
+    def h(): pass
+    return h
+";
+    assert!(resolve(source).1.is_empty());
+}
+
+fn unused(source: &str, options: &UnusedOptions) -> Vec<String> {
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let resolution = Resolver::new(module.syntax()).run().value;
+    resolution
+        .unused(module.syntax(), options)
+        .into_iter()
+        .map(|error| error.message)
+        .collect()
+}
+
+#[test]
+fn unused_symbols() {
+    let source = "\
+from __future__ import annotations
+import os, sys
+import json as json
+from typing import List, Dict as Dict
+from collections import OrderedDict
+import _thread
+__all__ = [\"OrderedDict\", \"_exported\"]
+def _helper(): pass
+def _exported(): pass
+def _used(): pass
+@register
+def _registered(): pass
+def public(a, _b, *args, **kwargs):
+    import re
+    x = 1
+    _y = 2
+    for i in range(3): pass
+    p, q = 1, 2
+    z = _used()
+    return z
+class C:
+    def m(self, c): ...
+";
+    assert_eq!(
+        unused(source, &UnusedOptions::default()),
+        &[
+            "'os' imported but unused",
+            "'sys' imported but unused",
+            "'typing.List' imported but unused",
+            "private function '_helper' is never used",
+            "'re' imported but unused",
+            "local variable 'x' is assigned to but never used",
+        ]
+    );
+    let options = UnusedOptions {
+        parameters: true,
+        ignore_underscore: false,
+    };
+    assert_eq!(
+        unused(source, &options),
+        &[
+            "'os' imported but unused",
+            "'sys' imported but unused",
+            "'typing.List' imported but unused",
+            "'_thread' imported but unused",
+            "private function '_helper' is never used",
+            "parameter 'a' is never used",
+            "parameter '_b' is never used",
+            "parameter 'args' is never used",
+            "parameter 'kwargs' is never used",
+            "'re' imported but unused",
+            "local variable 'x' is assigned to but never used",
+            "local variable '_y' is assigned to but never used",
+        ]
+    );
+}
+
+#[test]
+fn imports_used_in_string_annotations_are_used() {
+    let source = "\
+from typing import TYPE_CHECKING
+if TYPE_CHECKING:
+    from collections import OrderedDict
+    from os import PathLike
+    from re import Pattern
+def f(p: \"PathLike[str]\") -> \"list['OrderedDict']\": ...
+";
+    assert_eq!(
+        unused(source, &UnusedOptions::default()),
+        &["'re.Pattern' imported but unused"]
+    );
+}
+
+#[test]
+fn augmented_assignments_count_as_reads() {
+    let source = "\
+def f():
+    x = 0
+    x += 1
+    y = 0
+    def g():
+        nonlocal y
+        y += 1
+    g()
+";
+    assert!(unused(source, &UnusedOptions::default()).is_empty());
+}
+
+/// Describes the references to the first symbol named `name` as `access@line`.
+fn references(source: &str, name: &str) -> Vec<String> {
+    let (resolution, _) = resolve(source);
+    let id = resolution
+        .scopes
+        .iter()
+        .flat_map(|(_, scope)| scope.symbols())
+        .find(|(symbol_name, _)| *symbol_name == name)
+        .map(|(_, id)| id)
+        .unwrap();
+    resolution
+        .references(id)
+        .iter()
+        .map(|reference| format!("{:?}@{}", reference.access, line(source, reference.range)))
+        .collect()
+}
+
+#[test]
+fn references_are_classified_by_access() {
+    let source = "\
+import os.path
+x = 1
+def f(a, b: int):
+    global x
+    x += a
+    y: int
+    y = b
+    del y
+    return os.path.join(x, a)
+class C[T]:
+    def m(self, t: T): return t
+print(x, C)
+";
+    assert_eq!(
+        references(source, "x"),
+        &[
+            "Write@2",
+            "Declare@4",
+            "Read@5",
+            "Write@5",
+            "Read@9",
+            "Read@12"
+        ]
+    );
+    assert_eq!(references(source, "a"), &["Declare@3", "Read@5", "Read@9"]);
+    assert_eq!(
+        references(source, "y"),
+        &["Declare@6", "Write@7", "Delete@8"]
+    );
+    assert_eq!(references(source, "os"), &["Write@1", "Read@9"]);
+    assert_eq!(references(source, "T"), &["Declare@10", "Read@11"]);
+    assert_eq!(references(source, "C"), &["Declare@10", "Read@12"]);
+}
+
+#[test]
+fn query_scopes_symbols_and_nodes() {
+    let source = "\
+def f(x):
+    return [y for y in x]
+";
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let resolution = Resolver::new(module.syntax()).run().value;
+    let scopes = resolution.scopes();
+    let ast::Stmt::FunctionDef(func_def) = &module.syntax().body[0] else {
+        panic!("expected a function definition");
+    };
+
+    let function = resolution
+        .scope_created_by(func_def.node_index.load())
+        .unwrap();
+    assert_eq!(scopes.get(function).kind(), ScopeKind::Function);
+    assert_eq!(
+        scopes.ancestors(function).collect::<Vec<_>>(),
+        &[scopes.root_id(), scopes.builtins_id()]
+    );
+    let [comprehension] = scopes.get(function).children() else {
+        panic!("expected a single nested scope");
+    };
+    assert_eq!(scopes.get(*comprehension).kind(), ScopeKind::Comprehension);
+    assert_eq!(scopes.get(*comprehension).parent(), Some(function));
+
+    let x = scopes.get(function).get("x").unwrap();
+    assert_eq!(resolution.references(x).len(), 2);
+    for reference in resolution.references(x) {
+        assert_eq!(resolution.symbol_at(reference.node), Some(x));
+    }
+
+    let f = scopes.root().get("f").unwrap();
+    assert!(matches!(
+        resolution.symbols().get(f).decl_node(&module),
+        Some(ast::AnyRootNodeRef::Stmt(ast::Stmt::FunctionDef(def))) if def.name.id.as_str() == "f"
+    ));
+}
+
+#[test]
+fn query_the_scopes_nodes_are_evaluated_in() {
+    let source = "\
+@deco(a)
+def f[T: b](x: c = d) -> e:
+    return [y for y in g if h] + (lambda z=i: j)
+class C(k):
+    m = n
+o = 1
+";
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let resolution = Resolver::new(module.syntax()).run().value;
+    let kind = |node: ast::NodeIndex| {
+        let scope = resolution.scope_of(node).unwrap();
+        resolution.scopes().get(scope).kind()
+    };
+
+    let mut loads = Loads(Vec::new());
+    loads.visit_body(&module.syntax().body);
+    let loads: Vec<_> = loads
+        .0
+        .iter()
+        .map(|name| format!("{}@{:?}", name.id, kind(name.node_index.load())))
+        .collect();
+    assert_eq!(
+        loads,
+        &[
+            "deco@Module",
+            "a@Module",
+            "b@Annotation",
+            "c@Annotation",
+            "d@Module",
+            "e@Annotation",
+            "y@Comprehension",
+            "g@Function",
+            "h@Comprehension",
+            "i@Function",
+            "j@Lambda",
+            "k@Module",
+            "n@Class",
+        ]
+    );
+
+    let ast::Stmt::FunctionDef(func_def) = &module.syntax().body[0] else {
+        panic!("expected a function definition");
+    };
+    assert_eq!(kind(func_def.node_index.load()), ScopeKind::Module);
+    assert_eq!(kind(func_def.name.node_index.load()), ScopeKind::Module);
+    let x = &func_def.parameters.args[0].parameter;
+    assert_eq!(kind(x.name.node_index.load()), ScopeKind::Function);
+    let o = module.syntax().body[2].node_index().load();
+    assert_eq!(kind(o), ScopeKind::Module);
+    assert_eq!(resolution.scope_of(no_node_index()), None);
+}
+
+#[test]
+fn nodes_are_found_by_index() {
+    let module = IndexedModule::new(parser::parse_module("x = 1").unwrap());
+    let ast::Stmt::Assign(assign) = &module.syntax().body[0] else {
+        panic!("expected an assignment");
+    };
+    // The visited nodes are numbered from 1, the last one is the value of the assignment.
+    assert_eq!(assign.node_index.load().as_usize(), 1);
+    assert!(matches!(
+        module.get_by_index(assign.node_index.load()),
+        ast::AnyRootNodeRef::Stmt(ast::Stmt::Assign(_))
+    ));
+    let last = assign.value.node_index().load();
+    assert_eq!(last.as_usize(), 3);
+    assert!(matches!(
+        module.get_by_index(last),
+        ast::AnyRootNodeRef::Expr(ast::Expr::NumberLiteral(_))
+    ));
+}
@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+
+use ast::visitor::source_order::{SourceOrderVisitor, walk_expr, walk_stmt};
+use text_size::Ranged;
+
+use super::Resolution;
+use crate::{
+    error::{Errors, ErrorsBuilder, TypeError},
+    scope::ScopeKind,
+    symbol::{Access, Import, SymbolKind},
+};
+
+/// Which unused symbols [`Resolution::unused`] reports.
+#[derive(Clone, Copy, Debug)]
+pub struct UnusedOptions {
+    /// Whether to report unused parameters. Signatures are often dictated by an interface, so
+    /// this is off by default.
+    pub parameters: bool,
+    /// Whether to exempt variables, imports and parameters whose names start with `_`.
+    pub ignore_underscore: bool,
+}
+
+impl Default for UnusedOptions {
+    fn default() -> Self {
+        Self {
+            parameters: false,
+            ignore_underscore: true,
+        }
+    }
+}
+
+impl Resolution {
+    /// Reports the symbols that are bound but never read: local variables, imports, private
+    /// module-level functions and, optionally, parameters. `module` must be the module this is
+    /// the resolution of.
+    ///
+    /// Imports and private functions listed in `__all__` count as read, as do imports of the form
+    /// `import a as a` and `from a import b as b` at module level, which are explicit re-exports.
+    /// Forward references are not resolved, so imports whose names appear in string annotations,
+    /// e.g., `Foo` in `x: "list[Foo]"`, count as read too.
+    pub fn unused(&self, module: &ast::ModModule, options: &UnusedOptions) -> Errors {
+        // Augmented assignments read their targets without a load of the name.
+        let read: HashSet<_> = self
+            .references
+            .iter()
+            .filter(|(_, references)| {
+                references
+                    .iter()
+                    .any(|reference| reference.access == Access::Read)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        let mut facts = Facts::default();
+        facts.visit_body(&module.body);
+        let exported = dunder_all(&module.body);
+        let exempt = |name: &str| options.ignore_underscore && name.starts_with('_');
+
+        let mut errors = Vec::new();
+        for (_, scope) in self.scopes.iter() {
+            for (name, id) in scope.symbols() {
+                if read.contains(&id) {
+                    continue;
+                }
+                let symbol = self.symbols.get(id);
+                let message = match (symbol.kind, scope.kind()) {
+                    (SymbolKind::Import, ScopeKind::Module | ScopeKind::Function) => {
+                        let Some(import) = self.symbols.import(id) else {
+                            continue;
+                        };
+                        let module_level = scope.kind() == ScopeKind::Module;
+                        if exempt(name)
+                            || import.module.as_ref().map(|module| module.as_str())
+                                == Some("__future__")
+                            || (module_level && (exported.contains(name) || is_reexport(import)))
+                            || facts.forward_names.contains(name)
+                        {
+                            continue;
+                        }
+                        format!("'{}' imported but unused", qualified_name(import))
+                    }
+                    (SymbolKind::Variable, ScopeKind::Function) => {
+                        if exempt(name) || facts.unpacked.contains(&symbol.name) {
+                            continue;
+                        }
+                        format!("local variable '{name}' is assigned to but never used")
+                    }
+                    (SymbolKind::Function, ScopeKind::Module) => {
+                        let private = name.starts_with('_') && !name.starts_with("__");
+                        if !private
+                            || exported.contains(name)
+                            || facts.decorated.contains(&symbol.decl)
+                        {
+                            continue;
+                        }
+                        format!("private function '{name}' is never used")
+                    }
+                    (SymbolKind::Parameter, ScopeKind::Function | ScopeKind::Lambda) => {
+                        if !options.parameters
+                            || exempt(name)
+                            || matches!(name, "self" | "cls")
+                            || facts.stubs.contains(&scope.node())
+                        {
+                            continue;
+                        }
+                        format!("parameter '{name}' is never used")
+                    }
+                    _ => continue,
+                };
+                errors.push(TypeError::new(symbol.name_range, message));
+            }
+        }
+        errors.sort_by_key(|error| error.range.start());
+        let mut builder = ErrorsBuilder::new();
+        for error in errors {
+            builder.add(error);
+        }
+        builder.build()
+    }
+}
+
+/// The facts about the syntax tree that exempt symbols from being reported.
+#[derive(Default)]
+struct Facts {
+    /// The names bound by `for` loops and by destructuring assignments.
+    unpacked: HashSet<ast::NodeIndex>,
+    /// The decorated functions. Decorators often register the function somewhere.
+    decorated: HashSet<ast::NodeIndex>,
+    /// The functions whose bodies only consist of `pass`, `...`, docstrings and `raise`
+    /// statements, e.g., abstract methods.
+    stubs: HashSet<ast::NodeIndex>,
+    /// The names used in string annotations.
+    forward_names: HashSet<ast::name::Name>,
+}
+
+impl Facts {
+    fn add_unpacked(&mut self, target: &ast::Expr) {
+        match target {
+            ast::Expr::Name(name) => {
+                self.unpacked.insert(name.node_index.load());
+            }
+            ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+            | ast::Expr::List(ast::ExprList { elts, .. }) => {
+                for elt in elts {
+                    self.add_unpacked(elt);
+                }
+            }
+            ast::Expr::Starred(starred) => self.add_unpacked(&starred.value),
+            _ => {}
+        }
+    }
+}
+
+impl<'m> SourceOrderVisitor<'m> for Facts {
+    fn visit_stmt(&mut self, stmt: &'m ast::Stmt) {
+        match stmt {
+            ast::Stmt::For(for_stmt) => self.add_unpacked(&for_stmt.target),
+            ast::Stmt::Assign(assign) => {
+                for target in &assign.targets {
+                    if matches!(target, ast::Expr::Tuple(_) | ast::Expr::List(_)) {
+                        self.add_unpacked(target);
+                    }
+                }
+            }
+            ast::Stmt::FunctionDef(func_def) => {
+                let node = func_def.node_index.load();
+                if !func_def.decorator_list.is_empty() {
+                    self.decorated.insert(node);
+                }
+                if func_def.body.iter().all(is_stub) {
+                    self.stubs.insert(node);
+                }
+            }
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_annotation(&mut self, expr: &'m ast::Expr) {
+        let mut names = ForwardNames {
+            names: &mut self.forward_names,
+            in_string: false,
+        };
+        names.visit_expr(expr);
+    }
+}
+
+/// Collects the names in the string literals of an annotation, including nested ones like
+/// `"list['Foo']"`.
+struct ForwardNames<'a> {
+    names: &'a mut HashSet<ast::name::Name>,
+    in_string: bool,
+}
+
+impl<'m> SourceOrderVisitor<'m> for ForwardNames<'_> {
+    fn visit_expr(&mut self, expr: &'m ast::Expr) {
+        match expr {
+            ast::Expr::StringLiteral(literal) => {
+                // Invalid forward references are reported when the annotation is evaluated.
+                if let Ok(parsed) = parser::parse_expression(literal.value.to_str()) {
+                    let mut nested = ForwardNames {
+                        names: &mut *self.names,
+                        in_string: true,
+                    };
+                    nested.visit_expr(parsed.expr());
+                }
+            }
+            ast::Expr::Name(name) if self.in_string => {
+                self.names.insert(name.id.clone());
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+fn is_stub(stmt: &ast::Stmt) -> bool {
+    match stmt {
+        ast::Stmt::Pass(_) | ast::Stmt::Raise(_) => true,
+        ast::Stmt::Expr(expr) => matches!(
+            &*expr.value,
+            ast::Expr::EllipsisLiteral(_) | ast::Expr::StringLiteral(_)
+        ),
+        _ => false,
+    }
+}
+
+/// Collects the names listed in the module-level `__all__`.
+fn dunder_all(body: &[ast::Stmt]) -> HashSet<&str> {
+    let mut names = HashSet::new();
+    for stmt in body {
+        let (target, value) = match stmt {
+            ast::Stmt::Assign(ast::StmtAssign { targets, value, .. }) if targets.len() == 1 => {
+                (&targets[0], &**value)
+            }
+            ast::Stmt::AugAssign(ast::StmtAugAssign { target, value, .. }) => (&**target, &**value),
+            ast::Stmt::AnnAssign(ast::StmtAnnAssign { target, value: Some(value), .. }) => {
+                (&**target, &**value)
+            }
+            _ => continue,
+        };
+        if !matches!(target, ast::Expr::Name(name) if name.id.as_str() == "__all__") {
+            continue;
+        }
+        if let ast::Expr::List(ast::ExprList { elts, .. })
+        | ast::Expr::Tuple(ast::ExprTuple { elts, .. }) = value
+        {
+            for elt in elts {
+                if let ast::Expr::StringLiteral(literal) = elt {
+                    names.insert(literal.value.to_str());
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Whether the import is an explicit re-export, i.e., `import a as a` or `from a import b as b`.
+fn is_reexport(import: &Import) -> bool {
+    let imported = import.member.as_ref().or(import.module.as_ref());
+    import.asname.is_some() && import.asname.as_ref() == imported
+}
+
+/// The name of the imported module or member, e.g., `a.b` for `from a import b`.
+fn qualified_name(import: &Import) -> String {
+    let mut name = ".".repeat(import.level as usize);
+    if let Some(module) = &import.module {
+        name.push_str(module.as_str());
+    }
+    if let Some(member) = &import.member {
+        if import.module.is_some() {
+            name.push('.');
+        }
+        name.push_str(member.as_str());
+    }
+    name
+}
@@ -1,23 +1,75 @@
 #![allow(dead_code)]
-use std::collections::HashSet;
+use std::collections::HashMap;
 
-use crate::symbol::SymbolId;
+use crate::symbol::{SymbolId, no_node_index};
 
 pub use id::ScopeId;
 pub use table::ScopeTable;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScopeKind {
+    Builtins,
+    Module,
+    Class,
+    Function,
+    Lambda,
+    Comprehension,
+    /// The scope of the type parameters of a generic class, function or type alias (PEP 695).
+    Annotation,
+}
+
 #[derive(Debug)]
 pub struct Scope {
+    kind: ScopeKind,
     node: ast::NodeIndex,
     parent: Option<ScopeId>,
     children: Vec<ScopeId>,
-    symbols: HashSet<SymbolId>,
+    symbols: HashMap<ast::name::Name, SymbolId>,
+    star_import: bool,
+}
+
+impl Scope {
+    pub fn kind(&self) -> ScopeKind {
+        self.kind
+    }
+
+    /// The node creating the scope, e.g., the `def` statement of a function scope or the type
+    /// parameter list of an annotation scope. The builtins scope has no node.
+    pub fn node(&self) -> ast::NodeIndex {
+        self.node
+    }
+
+    /// The enclosing scope. Only the builtins scope has none.
+    pub fn parent(&self) -> Option<ScopeId> {
+        self.parent
+    }
+
+    /// The scopes directly nested in this scope, in source order.
+    pub fn children(&self) -> &[ScopeId] {
+        &self.children
+    }
+
+    /// The symbols of the scope together with their names, in no particular order.
+    pub fn symbols(&self) -> impl Iterator<Item = (&str, SymbolId)> {
+        self.symbols.iter().map(|(name, id)| (name.as_str(), *id))
+    }
+
+    /// Returns the symbol named `name` in this scope, without looking at enclosing scopes.
+    pub fn get(&self, name: &str) -> Option<SymbolId> {
+        self.symbols.get(name).copied()
+    }
+
+    /// Whether the scope contains a `from ... import *` statement, which binds names that are
+    /// not known statically.
+    pub fn has_star_import(&self) -> bool {
+        self.star_import
+    }
 }
 
 mod id {
     use std::num::NonZeroU16;
 
-    #[derive(Clone, Copy, Debug)]
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
     pub struct ScopeId(NonZeroU16);
 
     impl ScopeId {
@@ -38,21 +90,40 @@ mod table {
 
     #[derive(Debug)]
     pub struct ScopeTable {
+        builtins_id: ScopeId,
         root_id: ScopeId,
         scopes: Vec<Scope>,
+        /// Maps the nodes creating scopes to the scopes they create.
+        nodes: HashMap<ast::NodeIndex, ScopeId>,
     }
 
     impl ScopeTable {
         pub fn new(root_node: ast::NodeIndex) -> Self {
-            let root_id = ScopeId::from_index(0);
+            let builtins_id = ScopeId::from_index(0);
+            let root_id = ScopeId::from_index(1);
+            let builtins = Scope {
+                kind: ScopeKind::Builtins,
+                node: no_node_index(),
+                parent: None,
+                children: Vec::from([root_id]),
+                symbols: HashMap::new(),
+                star_import: false,
+            };
             let root = Scope {
+                kind: ScopeKind::Module,
                 node: root_node,
-                parent: None,
+                parent: Some(builtins_id),
                 children: Vec::new(),
-                symbols: HashSet::new(),
+                symbols: HashMap::new(),
+                star_import: false,
             };
-            let scopes = Vec::from([root]);
-            Self { root_id, scopes }
+            let scopes = Vec::from([builtins, root]);
+            let nodes = HashMap::from([(root_node, root_id)]);
+            Self { builtins_id, root_id, scopes, nodes }
+        }
+
+        pub fn builtins_id(&self) -> ScopeId {
+            self.builtins_id
         }
 
         pub fn root_id(&self) -> ScopeId {
@@ -60,27 +131,62 @@ mod table {
         }
 
         pub fn root(&self) -> &Scope {
-            &self.scopes[0]
+            self.get(self.root_id)
         }
 
-        pub fn make_scope(&mut self, node: ast::NodeIndex, parent: ScopeId) -> ScopeId {
+        pub fn make_scope(
+            &mut self,
+            kind: ScopeKind,
+            node: ast::NodeIndex,
+            parent: ScopeId,
+        ) -> ScopeId {
             let index = self.scopes.len();
             let scope = Scope {
+                kind,
                 node,
                 parent: Some(parent),
                 children: Vec::new(),
-                symbols: HashSet::new(),
+                symbols: HashMap::new(),
+                star_import: false,
             };
             self.scopes.push(scope);
-            ScopeId::from_index(index)
+            let id = ScopeId::from_index(index);
+            self.scopes[parent.into_index()].children.push(id);
+            self.nodes.insert(node, id);
+            id
         }
 
         pub fn get(&self, id: ScopeId) -> &Scope {
             &self.scopes[id.into_index()]
         }
 
-        pub fn add_symbol(&mut self, scope: ScopeId, symbol: SymbolId) -> bool {
-            self.scopes[scope.into_index()].symbols.insert(symbol)
+        /// Returns the scope created by `node`, e.g., by a `def` statement or a comprehension.
+        pub fn get_by_node(&self, node: ast::NodeIndex) -> Option<ScopeId> {
+            self.nodes.get(&node).copied()
+        }
+
+        /// Iterates over all scopes in the order they were created. Every scope comes before
+        /// the scopes nested in it.
+        pub fn iter(&self) -> impl Iterator<Item = (ScopeId, &Scope)> {
+            self.scopes
+                .iter()
+                .enumerate()
+                .map(|(index, scope)| (ScopeId::from_index(index), scope))
+        }
+
+        /// Iterates over the scopes enclosing the scope `id`, innermost first, ending with the
+        /// builtins scope.
+        pub fn ancestors(&self, id: ScopeId) -> impl Iterator<Item = ScopeId> {
+            std::iter::successors(self.get(id).parent, |id| self.get(*id).parent)
+        }
+
+        pub fn add_symbol(&mut self, scope: ScopeId, name: &str, symbol: SymbolId) -> bool {
+            let symbols = &mut self.scopes[scope.into_index()].symbols;
+            symbols.insert(name.into(), symbol).is_none()
+        }
+
+        pub fn add_star_import(&mut self, scope: ScopeId) {
+            self.scopes[scope.into_index()].star_import = true;
         }
     }
 }
@@ -4,6 +4,7 @@ use text_size::Ranged;
 pub use id::SymbolId;
 pub use table::SymbolTable;
 
+use crate::indexed::IndexedModule;
 use crate::scope::ScopeId;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -12,7 +13,14 @@ pub enum SymbolKind {
     Alias,
     Variable,
     Function,
+    Parameter,
+    Import,
+    Global,
     Nonlocal,
+    Builtin,
+    TypeVar,
+    ParamSpec,
+    TypeVarTuple,
 }
 
 impl std::fmt::Display for SymbolKind {
@@ -22,13 +30,20 @@ impl std::fmt::Display for SymbolKind {
             SymbolKind::Alias => "type alias",
             SymbolKind::Variable => "variable",
             SymbolKind::Function => "function",
+            SymbolKind::Parameter => "parameter",
+            SymbolKind::Import => "import",
+            SymbolKind::Global => "global",
             SymbolKind::Nonlocal => "nonlocal",
+            SymbolKind::Builtin => "builtin",
+            SymbolKind::TypeVar => "type variable",
+            SymbolKind::ParamSpec => "parameter specification",
+            SymbolKind::TypeVarTuple => "type variable tuple",
         };
         f.write_str(text)
     }
 }
 
-fn no_node_index() -> ast::NodeIndex {
+pub(crate) fn no_node_index() -> ast::NodeIndex {
     ast::AtomicNodeIndex::dummy().load()
 }
 
@@ -44,6 +59,122 @@ pub struct Symbol {
 
 static_assertions::const_assert_eq!(std::mem::size_of::<Symbol>(), 24);
 
+/// The details of a name bound by an `import` or `from ... import` statement.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Import {
+    /// The module path, e.g., `a.b` in `import a.b` and `from a.b import c`. It is `None` for
+    /// relative imports like `from . import c`.
+    pub module: Option<ast::name::Name>,
+    /// The imported member, e.g., `c` in `from a.b import c`. It is `None` for `import a.b`.
+    pub member: Option<ast::name::Name>,
+    /// The alias, e.g., `d` in `import a.b as d` and `from a.b import c as d`.
+    pub asname: Option<ast::name::Name>,
+    /// The number of leading dots of a relative import.
+    pub level: u32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ParameterKind {
+    PositionalOnly,
+    PositionalOrKeyword,
+    /// `*args`
+    VarPositional,
+    KeywordOnly,
+    /// `**kwargs`
+    VarKeyword,
+}
+
+/// The details of a parameter of a function or lambda.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Parameter {
+    pub kind: ParameterKind,
+    /// The annotation expression, e.g., `int` in `x: int = 0`.
+    pub annotation: Option<ast::NodeIndex>,
+    /// The default value expression, e.g., `0` in `x: int = 0`.
+    pub default: Option<ast::NodeIndex>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Access {
+    /// The value of the symbol is read, e.g., `x` in `print(x)`.
+    Read,
+    /// The symbol is bound, e.g., by an assignment, an import or a `for` loop.
+    Write,
+    /// The symbol is declared by a `def`, `class` or `type` statement, as a parameter or type
+    /// parameter, by an annotation without a value or by a `global` or `nonlocal` statement.
+    Declare,
+    /// The symbol is unbound by a `del` statement.
+    Delete,
+}
+
+/// An occurrence of a name referring to a symbol.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Reference {
+    pub node: ast::NodeIndex,
+    pub range: text_size::TextRange,
+    pub access: Access,
+}
+
+impl Reference {
+    pub(crate) fn make(node: impl HasNodeIndex + Ranged, access: Access) -> Self {
+        Self {
+            node: node.node_index().load(),
+            range: node.range(),
+            access,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Conflict {
+    /// Two definitions of the same name that cannot coexist.
+    Redefinition,
+    /// A name is assigned before it is declared `global` or `nonlocal`.
+    AssignedBeforeDeclaration,
+    /// A name is annotated and declared `global` or `nonlocal`.
+    AnnotatedDeclaration,
+    /// A parameter is declared `global` or `nonlocal`.
+    ParameterDeclaration,
+    /// Two parameters of the same function have the same name.
+    DuplicateParameter,
+    /// A name is declared both `global` and `nonlocal`.
+    GlobalAndNonlocal,
+}
+
+impl Conflict {
+    pub(crate) fn message(self, name: &str, earlier: &Symbol, later: &Symbol) -> String {
+        match self {
+            Conflict::Redefinition => format!(
+                "{} definition conflicts with earlier {} definition at {:?}",
+                later.kind, earlier.kind, earlier.name_range,
+            ),
+            Conflict::AssignedBeforeDeclaration => {
+                format!(
+                    "name '{name}' is assigned to before {} declaration",
+                    later.kind
+                )
+            }
+            Conflict::AnnotatedDeclaration => {
+                // The annotation can come before or after the declaration.
+                let declaration = if later.kind == SymbolKind::Variable {
+                    earlier.kind
+                } else {
+                    later.kind
+                };
+                format!("annotated name '{name}' can't be {declaration}")
+            }
+            Conflict::ParameterDeclaration => {
+                format!("name '{name}' is parameter and {}", later.kind)
+            }
+            Conflict::DuplicateParameter => {
+                format!("duplicate argument '{name}' in function definition")
+            }
+            Conflict::GlobalAndNonlocal => format!("name '{name}' is nonlocal and global"),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
 pub(crate) enum DeclOrDefn<T> {
     Decl(T),
     Defn(T),
@@ -55,10 +186,37 @@ impl Symbol {
         self.decl != no_node_index()
     }
 
-    // Returns whether the two symbols conflict.
-    pub(crate) fn merge(&self, later: &Symbol) -> (Option<Symbol>, bool) {
+    pub(crate) fn is_defn(&self) -> bool {
+        self.defn != no_node_index()
+    }
+
+    /// Whether the symbol only stands for a `del` statement, which makes a name local to its
+    /// scope but neither declares nor defines it.
+    fn is_deletion(&self) -> bool {
+        self.kind == SymbolKind::Variable && !self.is_decl() && !self.is_defn()
+    }
+
+    /// Returns the node declaring the symbol, e.g., the `def` statement of a function or the
+    /// annotated assignment of a variable. `module` must be the module the symbol comes from.
+    pub fn decl_node<'a>(&self, module: &'a IndexedModule) -> Option<ast::AnyRootNodeRef<'a>> {
+        self.is_decl().then(|| module.get_by_index(self.decl))
+    }
+
+    /// Returns the first node defining the symbol, e.g., the assignment binding a variable.
+    /// `module` must be the module the symbol comes from.
+    pub fn defn_node<'a>(&self, module: &'a IndexedModule) -> Option<ast::AnyRootNodeRef<'a>> {
+        self.is_defn().then(|| module.get_by_index(self.defn))
+    }
+
+    // Returns the merged symbol, if it differs from `self`, and the conflict between the two
+    // symbols, if any.
+    pub(crate) fn merge(&self, later: &Symbol) -> (Option<Symbol>, Option<Conflict>) {
+        use Conflict::*;
         use SymbolKind::*;
         match (self.kind, later.kind) {
+            // Deleting a name never conflicts with its definitions, e.g., `del f` after `def f`.
+            (_, Variable) if later.is_deletion() => (None, None),
+            (Variable, Function | Class) if self.is_deletion() => (Some(*later), None),
             (Variable, Variable) => {
                 let conflict = self.is_decl() && later.is_decl();
                 let decl = if self.is_decl() || !later.is_decl() {
@@ -68,11 +226,61 @@ impl Symbol {
                 };
                 let defn = ast::NodeIndex::min(self.defn, later.defn);
                 let merged = Symbol { defn, ..*decl };
-                (Some(merged), conflict)
+                (Some(merged), conflict.then_some(Redefinition))
             }
-            (Variable, Nonlocal) => (Some(*later), self.is_decl()),
-            (Nonlocal, Variable) => (None, self.is_decl()),
-            _ => (None, true),
+            (Parameter, Parameter) => (None, Some(DuplicateParameter)),
+            (Parameter, Variable | Import) => (None, later.is_decl().then_some(Redefinition)),
+            // Shadowing a parameter with a nested function or class is legal, if unusual.
+            (Parameter, Function | Class) => (None, None),
+            // Re-importing a name is harmless. The first import wins.
+            (Import, Import) => (None, None),
+            // Conditional imports with a fallback value, e.g., `except ImportError: json = None`,
+            // are common. Only an annotation is a conflict.
+            (Import, Variable) => (None, later.is_decl().then_some(Redefinition)),
+            // Fallback definitions like `except ImportError: def f(): ...` are just as common.
+            (Import, Function | Class) => (None, None),
+            (Variable, Import) => (None, self.is_decl().then_some(Redefinition)),
+            // Decorators like `@x.setter` and `@overload` and rebindings like `f = deco(f)` rebind
+            // functions and classes. Only an annotation is a conflict.
+            (Function | Class, Function | Class) => (None, None),
+            (Function | Class, Variable) => (None, later.is_decl().then_some(Redefinition)),
+            (Variable, Global | Nonlocal) => {
+                let conflict = if self.is_decl() {
+                    AnnotatedDeclaration
+                } else {
+                    AssignedBeforeDeclaration
+                };
+                (Some(*later), Some(conflict))
+            }
+            (Global | Nonlocal, Variable | Import) => {
+                // Bindings of a name declared `global` or `nonlocal` bind the name in the scope
+                // the declaration refers to. We only need to remember the earliest of them.
+                let defn = ast::NodeIndex::min(self.defn, later.defn);
+                let merged = Symbol { defn, ..*self };
+                (
+                    Some(merged),
+                    later.is_decl().then_some(AnnotatedDeclaration),
+                )
+            }
+            (Global | Nonlocal, Function | Class) => {
+                let defn = ast::NodeIndex::min(self.defn, later.decl);
+                (Some(Symbol { defn, ..*self }), None)
+            }
+            (Global, Global) | (Nonlocal, Nonlocal) => (None, None),
+            (Parameter, Global | Nonlocal) => (None, Some(ParameterDeclaration)),
+            (Global, Nonlocal) | (Nonlocal, Global) => (None, Some(GlobalAndNonlocal)),
+            _ => (None, Some(Redefinition)),
+        }
+    }
+
+    pub(crate) fn builtin(scope: ScopeId) -> Self {
+        Self {
+            kind: SymbolKind::Builtin,
+            scope,
+            name: no_node_index(),
+            name_range: text_size::TextRange::default(),
+            decl: no_node_index(),
+            defn: no_node_index(),
         }
     }
 
@@ -107,28 +315,83 @@ mod id {
 }
 
 mod table {
+    use std::collections::{HashMap, HashSet};
+
     use super::*;
 
     #[derive(Debug)]
-    pub struct SymbolTable(Vec<super::Symbol>);
+    pub struct SymbolTable {
+        symbols: Vec<Symbol>,
+        imports: HashMap<SymbolId, Import>,
+        parameters: HashMap<SymbolId, Parameter>,
+        /// The functions annotated to never return.
+        no_return: HashSet<SymbolId>,
+    }
 
     impl SymbolTable {
         pub fn new() -> Self {
-            Self(Vec::new())
+            Self {
+                symbols: Vec::new(),
+                imports: HashMap::new(),
+                parameters: HashMap::new(),
+                no_return: HashSet::new(),
+            }
         }
 
         pub fn insert(&mut self, symbol: Symbol) -> SymbolId {
-            let id = self.0.len().try_into().expect("More than 4G symbols? Wow!");
-            self.0.push(symbol);
+            let id = self
+                .symbols
+                .len()
+                .try_into()
+                .expect("More than 4G symbols? Wow!");
+            self.symbols.push(symbol);
             SymbolId(id)
         }
 
         pub fn get(&self, id: SymbolId) -> &Symbol {
-            &self.0[id.0 as usize]
+            &self.symbols[id.0 as usize]
         }
 
         pub fn get_mut(&mut self, id: SymbolId) -> &mut Symbol {
-            &mut self.0[id.0 as usize]
+            &mut self.symbols[id.0 as usize]
+        }
+
+        /// Returns the details of the import that bound the symbol, if it is an import symbol.
+        pub fn import(&self, id: SymbolId) -> Option<&Import> {
+            if self.get(id).kind == SymbolKind::Import {
+                self.imports.get(&id)
+            } else {
+                None
+            }
+        }
+
+        /// Records the details of an import symbol. Only the first import of a name is recorded.
+        pub(crate) fn insert_import(&mut self, id: SymbolId, import: Import) {
+            self.imports.entry(id).or_insert(import);
+        }
+
+        /// Returns the details of the parameter that bound the symbol, if it is a parameter symbol.
+        pub fn parameter(&self, id: SymbolId) -> Option<&Parameter> {
+            if self.get(id).kind == SymbolKind::Parameter {
+                self.parameters.get(&id)
+            } else {
+                None
+            }
+        }
+
+        /// Records the details of a parameter symbol. Only the first parameter of a name is
+        /// recorded.
+        pub(crate) fn insert_parameter(&mut self, id: SymbolId, parameter: Parameter) {
+            self.parameters.entry(id).or_insert(parameter);
+        }
+
+        /// Whether the symbol is a function annotated to return `NoReturn` or `Never`.
+        pub fn is_no_return(&self, id: SymbolId) -> bool {
+            self.no_return.contains(&id)
+        }
+
+        pub(crate) fn insert_no_return(&mut self, id: SymbolId) {
+            self.no_return.insert(id);
         }
     }
 }
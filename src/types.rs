@@ -0,0 +1,400 @@
+//! The representation of types. Types are interned in a [`TypeTable`] and referred to by their
+//! [`TypeId`], which makes them cheap to copy and compare.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Write};
+
+use crate::resolver::Resolution;
+use crate::symbol::{ParameterKind, SymbolId};
+
+pub use annotation::AnnotationEvaluator;
+pub use id::TypeId;
+pub use infer::{Inference, Inferrer};
+pub use table::TypeTable;
+
+mod annotation;
+mod infer;
+#[cfg(test)]
+mod tests;
+
+/// A class, identified by the symbol binding it. Builtin classes like `int` are identified by
+/// their builtin symbols.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Class {
+    pub symbol: SymbolId,
+    pub name: ast::name::Name,
+}
+
+impl Class {
+    /// Returns the builtin class `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a builtin.
+    pub(crate) fn builtin(resolution: &Resolution, name: &str) -> Self {
+        let scopes = resolution.scopes();
+        let symbol = scopes
+            .get(scopes.builtins_id())
+            .get(name)
+            .expect("Builtin classes are bound in the builtins scope.");
+        Self { symbol, name: name.into() }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Literal {
+    Bool(bool),
+    /// Integers that do not fit into an `i64` have no literal type, they are just `int`s.
+    Int(i64),
+    Str(Box<str>),
+    Bytes(Box<[u8]>),
+}
+
+/// A parameter of a callable type.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Parameter {
+    pub kind: ParameterKind,
+    /// The name of the parameter. Positional-only parameters might have none.
+    pub name: Option<ast::name::Name>,
+    /// The type of the parameter. For `*args` and `**kwargs`, it is the type of a single
+    /// argument.
+    pub ty: TypeId,
+    pub has_default: bool,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Type {
+    /// The gradual type, compatible with every other type.
+    Any,
+    /// The type without values, e.g., the return type of a function that never returns.
+    Never,
+    None,
+    Object,
+    /// An instance of a class with the given type arguments, e.g., `list[int]`.
+    Instance {
+        class: Class,
+        args: Box<[TypeId]>,
+    },
+    /// The class object of the given instance type, e.g., `type[int]`.
+    ClassObject(TypeId),
+    /// A union of at least two types. Unions are never nested and their elements are distinct.
+    Union(Box<[TypeId]>),
+    /// A callable with the given parameters or, if `None`, with any parameters, e.g.,
+    /// `Callable[..., int]`.
+    Callable {
+        parameters: Option<Box<[Parameter]>>,
+        returns: TypeId,
+    },
+    /// A tuple of fixed length, e.g., `tuple[int, str]`.
+    Tuple(Box<[TypeId]>),
+    /// A tuple of arbitrary length, e.g., `tuple[int, ...]`.
+    VariadicTuple(TypeId),
+    Literal(Literal),
+    /// A type variable, parameter specification or type variable tuple, identified by the
+    /// symbol binding it.
+    TypeVar {
+        symbol: SymbolId,
+        name: ast::name::Name,
+    },
+    /// A module object, e.g., the value bound by `import os`.
+    Module(ast::name::Name),
+}
+
+mod id {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct TypeId(pub(super) u32);
+}
+
+mod table {
+    use super::*;
+
+    #[derive(Debug)]
+    pub struct TypeTable {
+        types: Vec<Type>,
+        ids: HashMap<Type, TypeId>,
+    }
+
+    impl TypeTable {
+        pub const ANY: TypeId = TypeId(0);
+        pub const NEVER: TypeId = TypeId(1);
+        pub const NONE: TypeId = TypeId(2);
+        pub const OBJECT: TypeId = TypeId(3);
+
+        pub fn new() -> Self {
+            let mut table = Self {
+                types: Vec::new(),
+                ids: HashMap::new(),
+            };
+            for ty in [Type::Any, Type::Never, Type::None, Type::Object] {
+                table.intern(ty);
+            }
+            table
+        }
+
+        /// Returns the id of `ty`, adding it to the table if it is new. Equal types always get
+        /// the same id.
+        pub fn intern(&mut self, ty: Type) -> TypeId {
+            if let Some(id) = self.ids.get(&ty) {
+                return *id;
+            }
+            let id = TypeId(
+                self.types
+                    .len()
+                    .try_into()
+                    .expect("More than 4G types? Wow!"),
+            );
+            self.types.push(ty.clone());
+            self.ids.insert(ty, id);
+            id
+        }
+
+        pub fn get(&self, id: TypeId) -> &Type {
+            &self.types[id.0 as usize]
+        }
+
+        /// Returns the union of `elements`. Nested unions are flattened, duplicates and `Never`
+        /// are dropped and a union of a single type is that type.
+        pub fn union(&mut self, elements: impl IntoIterator<Item = TypeId>) -> TypeId {
+            let mut flattened = Vec::new();
+            for element in elements {
+                let nested = match self.get(element) {
+                    Type::Union(nested) => nested.to_vec(),
+                    Type::Never => Vec::new(),
+                    _ => Vec::from([element]),
+                };
+                for element in nested {
+                    if !flattened.contains(&element) {
+                        flattened.push(element);
+                    }
+                }
+            }
+            match flattened.as_slice() {
+                [] => Self::NEVER,
+                [single] => *single,
+                _ => self.intern(Type::Union(flattened.into_boxed_slice())),
+            }
+        }
+
+        pub fn instance(&mut self, class: Class, args: impl IntoIterator<Item = TypeId>) -> TypeId {
+            let args = args.into_iter().collect();
+            self.intern(Type::Instance { class, args })
+        }
+
+        /// Returns an object displaying the type in Python typing syntax.
+        pub fn display(&self, id: TypeId) -> DisplayType<'_> {
+            DisplayType { table: self, id }
+        }
+    }
+
+    impl Default for TypeTable {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+/// Displays a type in Python typing syntax, e.g., `list[int] | None`. Callables with parameters
+/// that cannot be expressed with `Callable` are displayed as signatures, e.g.,
+/// `(x: int, *, y: str = ...) -> bool`. Modules are displayed as `Module("os")`.
+pub struct DisplayType<'a> {
+    table: &'a TypeTable,
+    id: TypeId,
+}
+
+impl DisplayType<'_> {
+    fn with(&self, id: TypeId) -> Self {
+        Self { table: self.table, id }
+    }
+
+    fn write_list(&self, f: &mut fmt::Formatter<'_>, ids: &[TypeId]) -> fmt::Result {
+        for (index, id) in ids.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", self.with(*id))?;
+        }
+        Ok(())
+    }
+
+    fn write_signature(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        parameters: &[Parameter],
+        returns: TypeId,
+    ) -> fmt::Result {
+        f.write_char('(')?;
+        let mut keyword_only_marker = false;
+        for (index, parameter) in parameters.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            let name = parameter.name.as_ref().map_or("_", |name| name.as_str());
+            match parameter.kind {
+                ParameterKind::VarPositional => {
+                    keyword_only_marker = true;
+                    f.write_char('*')?;
+                }
+                ParameterKind::KeywordOnly if !keyword_only_marker => {
+                    keyword_only_marker = true;
+                    f.write_str("*, ")?;
+                }
+                ParameterKind::VarKeyword => f.write_str("**")?,
+                _ => {}
+            }
+            write!(f, "{name}: {}", self.with(parameter.ty))?;
+            if parameter.has_default {
+                f.write_str(" = ...")?;
+            }
+            let next = parameters.get(index + 1).map(|next| next.kind);
+            if parameter.kind == ParameterKind::PositionalOnly
+                && next != Some(ParameterKind::PositionalOnly)
+            {
+                f.write_str(", /")?;
+            }
+        }
+        write!(f, ") -> {}", self.with(returns))
+    }
+}
+
+/// Whether the parameters can be written as `Callable[[...], R]`, i.e., they are all
+/// positional-only, unnamed and required.
+fn is_simple(parameters: &[Parameter]) -> bool {
+    parameters.iter().all(|parameter| {
+        parameter.kind == ParameterKind::PositionalOnly
+            && parameter.name.is_none()
+            && !parameter.has_default
+    })
+}
+
+impl Display for DisplayType<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.table.get(self.id) {
+            Type::Any => f.write_str("Any"),
+            Type::Never => f.write_str("Never"),
+            Type::None => f.write_str("None"),
+            Type::Object => f.write_str("object"),
+            Type::Instance { class, args } => {
+                f.write_str(&class.name)?;
+                if !args.is_empty() {
+                    f.write_char('[')?;
+                    self.write_list(f, args)?;
+                    f.write_char(']')?;
+                }
+                Ok(())
+            }
+            Type::ClassObject(instance) => write!(f, "type[{}]", self.with(*instance)),
+            Type::Union(elements) => {
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(" | ")?;
+                    }
+                    // The return type of a signature would swallow the rest of the union.
+                    let signature = matches!(
+                        self.table.get(*element),
+                        Type::Callable { parameters: Some(parameters), .. } if !is_simple(parameters)
+                    );
+                    if signature {
+                        write!(f, "({})", self.with(*element))?;
+                    } else {
+                        write!(f, "{}", self.with(*element))?;
+                    }
+                }
+                Ok(())
+            }
+            Type::Callable { parameters, returns } => match parameters {
+                None => write!(f, "Callable[..., {}]", self.with(*returns)),
+                Some(parameters) if is_simple(parameters) => {
+                    f.write_str("Callable[[")?;
+                    let types: Vec<_> = parameters.iter().map(|parameter| parameter.ty).collect();
+                    self.write_list(f, &types)?;
+                    write!(f, "], {}]", self.with(*returns))
+                }
+                Some(parameters) => self.write_signature(f, parameters, *returns),
+            },
+            Type::Tuple(elements) if elements.is_empty() => f.write_str("tuple[()]"),
+            Type::Tuple(elements) => {
+                f.write_str("tuple[")?;
+                self.write_list(f, elements)?;
+                f.write_char(']')
+            }
+            Type::VariadicTuple(element) => write!(f, "tuple[{}, ...]", self.with(*element)),
+            Type::Literal(literal) => write!(f, "Literal[{literal}]"),
+            Type::TypeVar { name, .. } => f.write_str(name),
+            Type::Module(name) => write!(f, "Module(\"{name}\")"),
+        }
+    }
+}
+
+/// Displays the literal like Python's `repr` does, except that strings always use single quotes.
+impl Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Bool(true) => f.write_str("True"),
+            Literal::Bool(false) => f.write_str("False"),
+            Literal::Int(value) => write!(f, "{value}"),
+            Literal::Str(value) => {
+                f.write_char('\'')?;
+                for c in value.chars() {
+                    match c {
+                        '\'' => f.write_str("\\'")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c if c.is_control() => write!(f, "\\x{:02x}", c as u32)?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_char('\'')
+            }
+            Literal::Bytes(value) => {
+                f.write_str("b'")?;
+                for byte in value.iter() {
+                    match byte {
+                        b'\'' => f.write_str("\\'")?,
+                        b'\\' => f.write_str("\\\\")?,
+                        b'\n' => f.write_str("\\n")?,
+                        b'\r' => f.write_str("\\r")?,
+                        b'\t' => f.write_str("\\t")?,
+                        0x20..=0x7e => f.write_char(*byte as char)?,
+                        _ => write!(f, "\\x{byte:02x}")?,
+                    }
+                }
+                f.write_char('\'')
+            }
+        }
+    }
+}
+
+/// The declared and inferred types of symbols.
+#[derive(Debug, Default)]
+pub struct SymbolTypes {
+    declared: HashMap<SymbolId, TypeId>,
+    inferred: HashMap<SymbolId, TypeId>,
+}
+
+impl SymbolTypes {
+    /// Returns the type the symbol is declared with, e.g., by an annotation.
+    pub fn declared(&self, id: SymbolId) -> Option<TypeId> {
+        self.declared.get(&id).copied()
+    }
+
+    /// Records the declared type of a symbol. Only the first declaration is recorded.
+    pub fn insert_declared(&mut self, id: SymbolId, ty: TypeId) {
+        self.declared.entry(id).or_insert(ty);
+    }
+
+    /// Returns the union of the types of all values bound to the symbol.
+    pub fn inferred(&self, id: SymbolId) -> Option<TypeId> {
+        self.inferred.get(&id).copied()
+    }
+
+    /// Adds the type of a value bound to the symbol.
+    pub fn insert_inferred(&mut self, types: &mut TypeTable, id: SymbolId, ty: TypeId) {
+        let ty = match self.inferred.get(&id) {
+            Some(previous) => types.union([*previous, ty]),
+            None => ty,
+        };
+        self.inferred.insert(id, ty);
+    }
+}
@@ -0,0 +1,701 @@
+use std::collections::{HashMap, HashSet};
+
+use text_size::{Ranged, TextRange};
+
+use super::{Class, Literal, Parameter, Type, TypeId, TypeTable};
+use crate::{
+    builtins::is_builtin_class,
+    error::{Errors, ErrorsBuilder, Outcome, TypeError},
+    indexed::IndexedModule,
+    resolver::Resolution,
+    scope::{ScopeId, ScopeKind},
+    symbol::{Import, ParameterKind, SymbolId, SymbolKind},
+};
+
+/// The special forms of the `typing` module and the builtins that need special treatment in type
+/// expressions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum SpecialForm {
+    Any,
+    /// `Never` and `NoReturn`.
+    Never,
+    Optional,
+    Union,
+    Callable,
+    Literal,
+    /// `tuple` and `Tuple`.
+    Tuple,
+    /// `type` and `Type`.
+    Type,
+    TypeAlias,
+    /// The qualifiers wrapping the actual type, e.g., `ClassVar[int]` and `Annotated[int, ...]`.
+    Qualifier,
+    /// The deprecated aliases of builtin classes, e.g., `List` for `list`.
+    Alias(&'static str),
+    /// `TypeVar`, `ParamSpec` and `TypeVarTuple`, which are only allowed in assignments like
+    /// `T = TypeVar("T")`.
+    TypeVarConstructor(SymbolKind),
+}
+
+/// What a name or attribute in a type expression stands for.
+enum Form {
+    Special(SpecialForm),
+    Type(TypeId),
+}
+
+/// Lowers annotation expressions into types, using the names bound by the resolver. Type aliases,
+/// including implicit ones like `IntList = list[int]`, are expanded.
+///
+/// We do not analyze other modules, so we assume that everything imported from them, apart from
+/// the special forms of `typing`, is a class.
+pub struct AnnotationEvaluator<'a> {
+    module: &'a IndexedModule,
+    resolution: &'a Resolution,
+    types: &'a mut TypeTable,
+    /// The types of the type aliases expanded so far. `None` means that the symbol is a variable
+    /// not holding a type.
+    aliases: HashMap<SymbolId, Option<TypeId>>,
+    /// The type aliases we are currently expanding. Recursive type aliases become `Any`.
+    expanding: Vec<SymbolId>,
+    /// The variables bound to `ParamSpec(...)`.
+    param_specs: HashSet<SymbolId>,
+    /// The scope containing the annotation we are evaluating.
+    scope: ScopeId,
+    /// The range of the string literal if we are evaluating a forward reference. The nodes parsed
+    /// from the string are not indexed, so we resolve their names by looking them up in `scope`
+    /// and report their errors at the string.
+    forward: Option<TextRange>,
+    errors: ErrorsBuilder,
+}
+
+impl<'a> AnnotationEvaluator<'a> {
+    /// Creates an evaluator for annotations in `module`, which must be the module `resolution` is
+    /// the resolution of.
+    pub fn new(
+        module: &'a IndexedModule,
+        resolution: &'a Resolution,
+        types: &'a mut TypeTable,
+    ) -> Self {
+        Self {
+            module,
+            resolution,
+            types,
+            aliases: HashMap::new(),
+            expanding: Vec::new(),
+            param_specs: HashSet::new(),
+            scope: resolution.scopes().root_id(),
+            forward: None,
+            errors: ErrorsBuilder::new(),
+        }
+    }
+
+    /// Evaluates `annotation`, which appears in `scope`. Invalid parts of the annotation are
+    /// reported and evaluate to `Any`.
+    pub fn evaluate(&mut self, annotation: &ast::Expr, scope: ScopeId) -> Outcome<TypeId> {
+        self.scope = scope;
+        let ty = self.eval(annotation);
+        let errors = std::mem::replace(&mut self.errors, ErrorsBuilder::new());
+        Outcome::mixed(ty, errors)
+    }
+
+    pub fn types(&mut self) -> &mut TypeTable {
+        self.types
+    }
+
+    fn error(&mut self, range: TextRange, message: impl Into<String>) {
+        let range = self.forward.unwrap_or(range);
+        self.errors.add(TypeError::new(range, message));
+    }
+
+    fn invalid(&mut self, expr: &ast::Expr) -> TypeId {
+        let message = format!("{} is not allowed in a type expression", describe(expr));
+        self.error(expr.range(), message);
+        TypeTable::ANY
+    }
+
+    fn eval(&mut self, expr: &ast::Expr) -> TypeId {
+        match expr {
+            ast::Expr::NoneLiteral(_) => TypeTable::NONE,
+            ast::Expr::StringLiteral(string) => self.eval_forward(string),
+            ast::Expr::Name(_) | ast::Expr::Attribute(_) => match self.form(expr) {
+                Some(Form::Type(ty)) => ty,
+                Some(Form::Special(form)) => self.eval_bare(form, expr),
+                None => TypeTable::ANY,
+            },
+            ast::Expr::BinOp(binop) if binop.op == ast::Operator::BitOr => {
+                let left = self.eval(&binop.left);
+                let right = self.eval(&binop.right);
+                self.types.union([left, right])
+            }
+            ast::Expr::Subscript(subscript) => self.eval_subscript(subscript),
+            _ => self.invalid(expr),
+        }
+    }
+
+    fn eval_forward(&mut self, string: &ast::ExprStringLiteral) -> TypeId {
+        if string.value.is_implicit_concatenated() {
+            self.error(
+                string.range,
+                "forward references must be a single string literal",
+            );
+            return TypeTable::ANY;
+        }
+        let Ok(parsed) = parser::parse_expression(string.value.to_str()) else {
+            self.error(string.range, "forward reference is not a valid expression");
+            return TypeTable::ANY;
+        };
+        // Errors in nested forward references are reported at the outermost string.
+        let forward = self.forward.replace(self.forward.unwrap_or(string.range));
+        let ty = self.eval(parsed.expr());
+        self.forward = forward;
+        ty
+    }
+
+    /// Evaluates a special form used without type arguments.
+    fn eval_bare(&mut self, form: SpecialForm, expr: &ast::Expr) -> TypeId {
+        match form {
+            SpecialForm::Any | SpecialForm::Qualifier | SpecialForm::TypeAlias => TypeTable::ANY,
+            SpecialForm::Never => TypeTable::NEVER,
+            SpecialForm::Tuple => self.types.intern(Type::VariadicTuple(TypeTable::ANY)),
+            SpecialForm::Type => self.types.intern(Type::ClassObject(TypeTable::ANY)),
+            SpecialForm::Callable => self.types.intern(Type::Callable {
+                parameters: None,
+                returns: TypeTable::ANY,
+            }),
+            SpecialForm::Alias(name) => self.builtin(name, []),
+            SpecialForm::Optional | SpecialForm::Union | SpecialForm::Literal => {
+                let message = format!("'{}' requires type arguments", last_name(expr));
+                self.error(expr.range(), message);
+                TypeTable::ANY
+            }
+            SpecialForm::TypeVarConstructor(_) => {
+                self.not_allowed(expr.range(), "class", last_name(expr));
+                TypeTable::ANY
+            }
+        }
+    }
+
+    fn eval_subscript(&mut self, subscript: &ast::ExprSubscript) -> TypeId {
+        let args: Vec<&ast::Expr> = match &*subscript.slice {
+            ast::Expr::Tuple(tuple) => tuple.elts.iter().collect(),
+            slice => Vec::from([slice]),
+        };
+        let form = match &*subscript.value {
+            value @ (ast::Expr::Name(_) | ast::Expr::Attribute(_)) => self.form(value),
+            value => {
+                self.invalid(value);
+                None
+            }
+        };
+        let value = &*subscript.value;
+        let form = match form {
+            Some(Form::Special(form)) => form,
+            Some(Form::Type(ty)) => return self.eval_generic(ty, value, &args),
+            None => return TypeTable::ANY,
+        };
+        match form {
+            SpecialForm::Optional if args.len() == 1 => {
+                let ty = self.eval(args[0]);
+                self.types.union([ty, TypeTable::NONE])
+            }
+            SpecialForm::Type if args.len() == 1 => {
+                let ty = self.eval(args[0]);
+                self.types.intern(Type::ClassObject(ty))
+            }
+            SpecialForm::Optional | SpecialForm::Type => {
+                let message = format!("'{}' expects a single type argument", last_name(value));
+                self.error(subscript.range, message);
+                TypeTable::ANY
+            }
+            SpecialForm::Union => {
+                let elements: Vec<_> = args.iter().map(|arg| self.eval(arg)).collect();
+                self.types.union(elements)
+            }
+            SpecialForm::Callable => self.eval_callable(subscript, &args),
+            SpecialForm::Literal => {
+                let mut elements = Vec::new();
+                for arg in args {
+                    self.eval_literal(arg, &mut elements);
+                }
+                self.types.union(elements)
+            }
+            SpecialForm::Tuple => self.eval_tuple(&args),
+            // Only the first argument of `Annotated` is a type, the others are metadata.
+            SpecialForm::Qualifier => match args.first() {
+                Some(arg) => self.eval(arg),
+                None => {
+                    let message = format!("'{}' expects a type argument", last_name(value));
+                    self.error(subscript.range, message);
+                    TypeTable::ANY
+                }
+            },
+            SpecialForm::Alias(name) => {
+                let args: Vec<_> = args.iter().map(|arg| self.eval(arg)).collect();
+                self.builtin(name, args)
+            }
+            SpecialForm::Any
+            | SpecialForm::Never
+            | SpecialForm::TypeAlias
+            | SpecialForm::TypeVarConstructor(_) => {
+                let message = format!("'{}' is not generic", last_name(value));
+                self.error(value.range(), message);
+                TypeTable::ANY
+            }
+        }
+    }
+
+    /// Applies the class `ty` to type arguments, e.g., `list` to `int` in `list[int]`.
+    fn eval_generic(&mut self, ty: TypeId, value: &ast::Expr, args: &[&ast::Expr]) -> TypeId {
+        match self.types.get(ty) {
+            Type::Instance { class, args: existing } if existing.is_empty() => {
+                let class = class.clone();
+                let args: Vec<_> = args.iter().map(|arg| self.eval(arg)).collect();
+                self.types.instance(class, args)
+            }
+            _ => {
+                let message = format!("'{}' is not generic", self.types.display(ty));
+                self.error(value.range(), message);
+                TypeTable::ANY
+            }
+        }
+    }
+
+    fn eval_callable(&mut self, subscript: &ast::ExprSubscript, args: &[&ast::Expr]) -> TypeId {
+        let [parameters, returns] = args else {
+            self.error(
+                subscript.range,
+                "'Callable' expects a list of parameter types and a return type",
+            );
+            return TypeTable::ANY;
+        };
+        let parameters = match parameters {
+            ast::Expr::EllipsisLiteral(_) => None,
+            ast::Expr::List(list) => Some(
+                list.elts
+                    .iter()
+                    .map(|elt| Parameter {
+                        kind: ParameterKind::PositionalOnly,
+                        name: None,
+                        ty: self.eval(elt),
+                        has_default: false,
+                    })
+                    .collect(),
+            ),
+            _ => {
+                let ty = self.eval(parameters);
+                let is_param_spec = matches!(
+                    self.types.get(ty),
+                    Type::TypeVar { symbol, .. } if self.is_param_spec(*symbol)
+                );
+                if !is_param_spec {
+                    self.error(
+                        parameters.range(),
+                        "'Callable' expects a list of parameter types, '...' or a parameter \
+                         specification",
+                    );
+                }
+                None
+            }
+        };
+        let returns = self.eval(returns);
+        self.types.intern(Type::Callable { parameters, returns })
+    }
+
+    fn is_param_spec(&self, symbol: SymbolId) -> bool {
+        self.resolution.symbols().get(symbol).kind == SymbolKind::ParamSpec
+            || self.param_specs.contains(&symbol)
+    }
+
+    /// Adds the types of the value `expr` in `Literal[...]` to `elements`.
+    fn eval_literal(&mut self, expr: &ast::Expr, elements: &mut Vec<TypeId>) {
+        if let Some(value) = int_literal(expr) {
+            let ty = match value {
+                Some(value) => self.types.intern(Type::Literal(Literal::Int(value))),
+                None => self.builtin("int", []),
+            };
+            elements.push(ty);
+            return;
+        }
+        let literal = match expr {
+            ast::Expr::NoneLiteral(_) => {
+                elements.push(TypeTable::NONE);
+                return;
+            }
+            ast::Expr::BooleanLiteral(literal) => Literal::Bool(literal.value),
+            ast::Expr::StringLiteral(literal) => Literal::Str(literal.value.to_str().into()),
+            ast::Expr::BytesLiteral(literal) => Literal::Bytes(literal.value.bytes().collect()),
+            // Nested literals are flattened, e.g., `Literal[Literal[1], 2]`.
+            ast::Expr::Subscript(subscript)
+                if self.special(&subscript.value) == Some(SpecialForm::Literal) =>
+            {
+                elements.push(self.eval_subscript(subscript));
+                return;
+            }
+            _ => {
+                let message = format!("{} is not allowed in a 'Literal' type", describe(expr));
+                self.error(expr.range(), message);
+                return;
+            }
+        };
+        elements.push(self.types.intern(Type::Literal(literal)));
+    }
+
+    fn eval_tuple(&mut self, args: &[&ast::Expr]) -> TypeId {
+        match args {
+            [element, ast::Expr::EllipsisLiteral(_)] => {
+                let element = self.eval(element);
+                self.types.intern(Type::VariadicTuple(element))
+            }
+            // `tuple[()]` has no arguments at all.
+            _ => {
+                let elements = args.iter().map(|arg| self.eval(arg)).collect();
+                self.types.intern(Type::Tuple(elements))
+            }
+        }
+    }
+
+    /// Returns what the name or attribute `expr` stands for. Reports an error and returns `None`
+    /// if it is neither a type nor a special form. Undefined names are only reported in forward
+    /// references, the resolver reports all others.
+    fn form(&mut self, expr: &ast::Expr) -> Option<Form> {
+        let imported = self.imported(expr);
+        if let Some((_, qualified)) = &imported
+            && let Some(form) = special_form(qualified)
+        {
+            return Some(Form::Special(form));
+        }
+        match expr {
+            ast::Expr::Name(name) => {
+                let Some(id) = self.lookup(name) else {
+                    // The name might be bound by a `from ... import *`.
+                    let scopes = self.resolution.scopes();
+                    if self.forward.is_some()
+                        && !self
+                            .visible_scopes()
+                            .any(|id| scopes.get(id).has_star_import())
+                    {
+                        self.error(name.range, format!("name '{}' is not defined", name.id));
+                    }
+                    return None;
+                };
+                self.symbol_form(id, name.id.as_str(), name.range)
+            }
+            ast::Expr::Attribute(attribute) => match imported {
+                Some((symbol, _)) => {
+                    let class = Class {
+                        symbol,
+                        name: attribute.attr.id.clone(),
+                    };
+                    Some(Form::Type(self.types.instance(class, [])))
+                }
+                None => self.member_form(attribute),
+            },
+            _ => {
+                self.invalid(expr);
+                None
+            }
+        }
+    }
+
+    /// Returns what a class member like `Outer.Inner` stands for.
+    fn member_form(&mut self, attribute: &ast::ExprAttribute) -> Option<Form> {
+        let Form::Type(ty) = self.form(&attribute.value)? else {
+            self.invalid(&attribute.value);
+            return None;
+        };
+        let symbols = self.resolution.symbols();
+        let member = match self.types.get(ty) {
+            Type::Instance { class, args } if args.is_empty() => {
+                let symbol = symbols.get(class.symbol);
+                (symbol.kind == SymbolKind::Class)
+                    .then(|| self.resolution.scope_created_by(symbol.decl))
+                    .flatten()
+                    .and_then(|scope| {
+                        let scope = self.resolution.scopes().get(scope);
+                        scope.get(attribute.attr.as_str())
+                    })
+            }
+            _ => None,
+        };
+        match member {
+            Some(id) => self.symbol_form(id, attribute.attr.as_str(), attribute.range),
+            None => {
+                let message = format!(
+                    "'{}' has no member '{}' that is a type",
+                    self.types.display(ty),
+                    attribute.attr
+                );
+                self.error(attribute.range, message);
+                None
+            }
+        }
+    }
+
+    fn symbol_form(&mut self, id: SymbolId, name: &str, range: TextRange) -> Option<Form> {
+        let symbol = *self.resolution.symbols().get(id);
+        let class = Class { symbol: id, name: name.into() };
+        let ty = match symbol.kind {
+            SymbolKind::Builtin => match name {
+                "object" => TypeTable::OBJECT,
+                "tuple" => return Some(Form::Special(SpecialForm::Tuple)),
+                "type" => return Some(Form::Special(SpecialForm::Type)),
+                _ if is_builtin_class(name) => self.types.instance(class, []),
+                _ => return self.not_allowed(range, symbol.kind, name),
+            },
+            SymbolKind::Class => self.types.instance(class, []),
+            SymbolKind::TypeVar | SymbolKind::ParamSpec | SymbolKind::TypeVarTuple => self
+                .types
+                .intern(Type::TypeVar { symbol: id, name: name.into() }),
+            SymbolKind::Import => match self.resolution.symbols().import(id) {
+                Some(Import { member: Some(_), .. }) => self.types.instance(class, []),
+                _ => return self.not_allowed(range, "module", name),
+            },
+            SymbolKind::Alias | SymbolKind::Variable => match self.expand(id, name) {
+                Some(ty) => ty,
+                None => return self.not_allowed(range, symbol.kind, name),
+            },
+            kind => return self.not_allowed(range, kind, name),
+        };
+        Some(Form::Type(ty))
+    }
+
+    fn not_allowed(
+        &mut self,
+        range: TextRange,
+        what: impl std::fmt::Display,
+        name: &str,
+    ) -> Option<Form> {
+        let message = format!("{what} '{name}' is not allowed in a type expression");
+        self.error(range, message);
+        None
+    }
+
+    /// Returns the type the type alias or variable `id` stands for, or `None` if it is a variable
+    /// not holding a type.
+    fn expand(&mut self, id: SymbolId, name: &str) -> Option<TypeId> {
+        if let Some(ty) = self.aliases.get(&id) {
+            return *ty;
+        }
+        if self.expanding.contains(&id) {
+            return Some(TypeTable::ANY);
+        }
+        let symbol = *self.resolution.symbols().get(id);
+        // The value of the alias is in the module itself and has its own errors, which are not
+        // the errors of the annotation we are evaluating.
+        let saved = (
+            self.scope,
+            self.forward.take(),
+            std::mem::replace(&mut self.errors, ErrorsBuilder::new()),
+        );
+        self.scope = symbol.scope;
+        self.expanding.push(id);
+        let ty = self.expand_value(id, name);
+        self.expanding.pop();
+        (self.scope, self.forward, self.errors) = saved;
+        self.aliases.insert(id, ty);
+        ty
+    }
+
+    fn expand_value(&mut self, id: SymbolId, name: &str) -> Option<TypeId> {
+        let module = self.module;
+        let resolution = self.resolution;
+        let symbol = resolution.symbols().get(id);
+        let value: &ast::Expr = match (symbol.decl_node(module), symbol.defn_node(module)) {
+            (Some(ast::AnyRootNodeRef::Stmt(ast::Stmt::TypeAlias(alias))), _) => &alias.value,
+            // An explicit type alias like `IntList: TypeAlias = list[int]`.
+            (Some(ast::AnyRootNodeRef::Stmt(ast::Stmt::AnnAssign(assign))), _) => {
+                match &assign.value {
+                    Some(value)
+                        if self.special(&assign.annotation) == Some(SpecialForm::TypeAlias) =>
+                    {
+                        value
+                    }
+                    _ => return None,
+                }
+            }
+            // An implicit type alias like `IntList = list[int]` or a type variable like
+            // `T = TypeVar("T")`.
+            (None, Some(ast::AnyRootNodeRef::Stmt(ast::Stmt::Assign(assign))))
+                if assign.targets.len() == 1 =>
+            {
+                &assign.value
+            }
+            _ => return None,
+        };
+        if let ast::Expr::Call(call) = value {
+            let Some(SpecialForm::TypeVarConstructor(kind)) = self.special(&call.func) else {
+                return None;
+            };
+            if kind == SymbolKind::ParamSpec {
+                self.param_specs.insert(id);
+            }
+            return Some(
+                self.types
+                    .intern(Type::TypeVar { symbol: id, name: name.into() }),
+            );
+        }
+        let ty = self.eval(value);
+        let errors = std::mem::replace(&mut self.errors, ErrorsBuilder::new()).build();
+        matches!(errors, Errors::AllGood).then_some(ty)
+    }
+
+    /// Returns the special form the name or attribute `expr` refers to, if any.
+    fn special(&self, expr: &ast::Expr) -> Option<SpecialForm> {
+        let (_, qualified) = self.imported(expr)?;
+        special_form(&qualified)
+    }
+
+    /// Returns the import symbol the name or attribute `expr` is based on and the fully
+    /// qualified name of what it refers to, e.g., `typing.Optional` for `Optional` after
+    /// `from typing import Optional` and for `t.Optional` after `import typing as t`.
+    fn imported(&self, expr: &ast::Expr) -> Option<(SymbolId, String)> {
+        match expr {
+            ast::Expr::Name(name) => {
+                let id = self.lookup(name)?;
+                let import = self.resolution.symbols().import(id)?;
+                if import.level > 0 {
+                    return None;
+                }
+                let qualified = match (&import.module, &import.member) {
+                    (Some(module), Some(member)) => format!("{module}.{member}"),
+                    (Some(module), None) if import.asname.is_some() => module.to_string(),
+                    // `import a.b` binds `a`.
+                    (Some(_), None) => name.id.to_string(),
+                    (None, _) => return None,
+                };
+                Some((id, qualified))
+            }
+            ast::Expr::Attribute(attribute) => {
+                let (id, qualified) = self.imported(&attribute.value)?;
+                Some((id, format!("{qualified}.{}", attribute.attr)))
+            }
+            _ => None,
+        }
+    }
+
+    fn lookup(&self, name: &ast::ExprName) -> Option<SymbolId> {
+        if self.forward.is_none() {
+            return self.resolution.symbol_at(name.node_index.load());
+        }
+        // Forward references are resolved once the module has been executed completely.
+        let scopes = self.resolution.scopes();
+        let id = self
+            .visible_scopes()
+            .find_map(|id| scopes.get(id).get(name.id.as_str()))?;
+        Some(self.resolution.referent(id).unwrap_or(id))
+    }
+
+    /// Returns the scopes visible from the annotation, innermost first. Like for other loads,
+    /// enclosing class scopes are not visible.
+    fn visible_scopes(&self) -> impl Iterator<Item = ScopeId> {
+        let scopes = self.resolution.scopes();
+        std::iter::once(self.scope).chain(
+            scopes
+                .ancestors(self.scope)
+                .filter(|id| scopes.get(*id).kind() != ScopeKind::Class),
+        )
+    }
+
+    fn builtin(&mut self, name: &str, args: impl IntoIterator<Item = TypeId>) -> TypeId {
+        let class = Class::builtin(self.resolution, name);
+        self.types.instance(class, args)
+    }
+}
+
+fn special_form(qualified: &str) -> Option<SpecialForm> {
+    let (module, name) = qualified.rsplit_once('.')?;
+    let form = match (module, name) {
+        ("collections.abc", "Callable") => return Some(SpecialForm::Callable),
+        ("typing" | "typing_extensions", name) => name,
+        _ => return None,
+    };
+    let form = match form {
+        "Any" => SpecialForm::Any,
+        "Never" | "NoReturn" => SpecialForm::Never,
+        "Optional" => SpecialForm::Optional,
+        "Union" => SpecialForm::Union,
+        "Callable" => SpecialForm::Callable,
+        "Literal" => SpecialForm::Literal,
+        "Tuple" => SpecialForm::Tuple,
+        "Type" => SpecialForm::Type,
+        "TypeAlias" => SpecialForm::TypeAlias,
+        "ClassVar" | "Final" | "Annotated" | "Required" | "NotRequired" | "ReadOnly" => {
+            SpecialForm::Qualifier
+        }
+        "List" => SpecialForm::Alias("list"),
+        "Dict" => SpecialForm::Alias("dict"),
+        "Set" => SpecialForm::Alias("set"),
+        "FrozenSet" => SpecialForm::Alias("frozenset"),
+        "TypeVar" => SpecialForm::TypeVarConstructor(SymbolKind::TypeVar),
+        "ParamSpec" => SpecialForm::TypeVarConstructor(SymbolKind::ParamSpec),
+        "TypeVarTuple" => SpecialForm::TypeVarConstructor(SymbolKind::TypeVarTuple),
+        _ => return None,
+    };
+    Some(form)
+}
+
+/// Returns the value of an integer literal like `1` or `-1`. The inner option is `None` if the
+/// value does not fit into an `i64`.
+fn int_literal(expr: &ast::Expr) -> Option<Option<i64>> {
+    match expr {
+        ast::Expr::NumberLiteral(ast::ExprNumberLiteral {
+            value: ast::Number::Int(int), ..
+        }) => Some(int.as_i64()),
+        ast::Expr::UnaryOp(ast::ExprUnaryOp { op: ast::UnaryOp::USub, operand, .. }) => {
+            match &**operand {
+                ast::Expr::NumberLiteral(ast::ExprNumberLiteral {
+                    value: ast::Number::Int(int),
+                    ..
+                }) => Some(int.as_i64().and_then(i64::checked_neg)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The last name in a name or attribute, e.g., `Optional` in `typing.Optional`.
+fn last_name(expr: &ast::Expr) -> &str {
+    match expr {
+        ast::Expr::Name(name) => name.id.as_str(),
+        ast::Expr::Attribute(attribute) => attribute.attr.as_str(),
+        _ => "?",
+    }
+}
+
+fn describe(expr: &ast::Expr) -> &'static str {
+    match expr {
+        ast::Expr::BoolOp(_) => "boolean operation",
+        ast::Expr::Named(_) => "assignment expression",
+        ast::Expr::BinOp(_) => "binary operation",
+        ast::Expr::UnaryOp(_) => "unary operation",
+        ast::Expr::Lambda(_) => "lambda",
+        ast::Expr::If(_) => "conditional expression",
+        ast::Expr::Dict(_) => "dictionary expression",
+        ast::Expr::Set(_) => "set expression",
+        ast::Expr::ListComp(_)
+        | ast::Expr::SetComp(_)
+        | ast::Expr::DictComp(_)
+        | ast::Expr::Generator(_) => "comprehension",
+        ast::Expr::Await(_) => "await expression",
+        ast::Expr::Yield(_) | ast::Expr::YieldFrom(_) => "yield expression",
+        ast::Expr::Compare(_) => "comparison",
+        ast::Expr::Call(_) => "call expression",
+        ast::Expr::FString(_) => "f-string",
+        ast::Expr::TString(_) => "t-string",
+        ast::Expr::StringLiteral(_) => "string literal",
+        ast::Expr::BytesLiteral(_) => "bytes literal",
+        ast::Expr::NumberLiteral(_) => "number literal",
+        ast::Expr::BooleanLiteral(_) => "boolean literal",
+        ast::Expr::NoneLiteral(_) => "'None'",
+        ast::Expr::EllipsisLiteral(_) => "'...'",
+        ast::Expr::Attribute(_) => "attribute access",
+        ast::Expr::Subscript(_) => "subscript",
+        ast::Expr::Starred(_) => "starred expression",
+        ast::Expr::Name(_) => "name",
+        ast::Expr::List(_) => "list expression",
+        ast::Expr::Tuple(_) => "tuple expression",
+        ast::Expr::Slice(_) => "slice",
+        ast::Expr::IpyEscapeCommand(_) => "escape command",
+    }
+}
@@ -0,0 +1,1291 @@
+use std::collections::HashMap;
+
+use ast::HasNodeIndex;
+use ast::visitor::source_order::{SourceOrderVisitor, walk_expr, walk_stmt};
+use text_size::Ranged;
+
+use super::{AnnotationEvaluator, Class, Literal, Parameter, SymbolTypes, Type, TypeId, TypeTable};
+use crate::{
+    builtins::{is_builtin_class, is_builtin_subclass},
+    error::{ErrorsBuilder, Outcome, TypeError},
+    indexed::IndexedModule,
+    resolver::Resolution,
+    scope::ScopeId,
+    symbol::{ParameterKind, SymbolId, SymbolKind},
+};
+
+/// The result of type inference: the types of the expressions of a module and the declared and
+/// inferred types of its symbols.
+#[derive(Debug)]
+pub struct Inference {
+    types: TypeTable,
+    /// Maps the expressions to their types. Annotations are not evaluated like other expressions
+    /// and have no types here.
+    expressions: HashMap<ast::NodeIndex, TypeId>,
+    symbols: SymbolTypes,
+}
+
+impl Inference {
+    pub fn types(&self) -> &TypeTable {
+        &self.types
+    }
+
+    /// Returns the type of the expression at `node`. The type of a target of an assignment is the
+    /// type of the value it is bound to.
+    pub fn expression_type(&self, node: ast::NodeIndex) -> Option<TypeId> {
+        self.expressions.get(&node).copied()
+    }
+
+    /// Returns the type of the symbol: its declared type if it has one and otherwise the union of
+    /// the types of the values bound to it.
+    pub fn symbol_type(&self, id: SymbolId) -> Option<TypeId> {
+        self.symbols
+            .declared(id)
+            .or_else(|| self.symbols.inferred(id))
+    }
+
+    pub fn symbol_types(&self) -> &SymbolTypes {
+        &self.symbols
+    }
+}
+
+/// Infers the types of all expressions in a module in a single pass in source order. A name has
+/// the declared type of its symbol or, if there is none, the union of the types of the values
+/// bound to the symbol so far.
+///
+/// We do not analyze other modules and know nothing about builtin functions, so all expressions
+/// involving them are `Any`.
+pub struct Inferrer<'m> {
+    module: &'m IndexedModule,
+    resolution: &'m Resolution,
+    inference: Inference,
+    errors: ErrorsBuilder,
+    /// The scope of the code we are visiting.
+    scope: ScopeId,
+    /// The instance type of the class whose body we are directly in, if any.
+    class: Option<TypeId>,
+}
+
+impl<'m> Inferrer<'m> {
+    /// Creates an inferrer for `module`, which must be the module `resolution` is the resolution
+    /// of.
+    pub fn new(module: &'m IndexedModule, resolution: &'m Resolution) -> Self {
+        let inference = Inference {
+            types: TypeTable::new(),
+            expressions: HashMap::new(),
+            symbols: SymbolTypes::default(),
+        };
+        Self {
+            module,
+            resolution,
+            inference,
+            errors: ErrorsBuilder::new(),
+            scope: resolution.scopes().root_id(),
+            class: None,
+        }
+    }
+
+    /// Runs the inference. The errors are the ones in annotations and the assignments of values
+    /// that are not assignable to the declared types of the variables.
+    pub fn run(mut self) -> Outcome<Inference> {
+        let module = self.module;
+        self.visit_body(&module.syntax().body);
+        Outcome::mixed(self.inference, self.errors)
+    }
+
+    pub(super) fn types(&mut self) -> &mut TypeTable {
+        &mut self.inference.types
+    }
+
+    fn record(&mut self, expr: &ast::Expr, ty: TypeId) -> TypeId {
+        self.inference
+            .expressions
+            .insert(expr.node_index().load(), ty);
+        ty
+    }
+
+    fn annotation(&mut self, annotation: &ast::Expr, scope: ScopeId) -> TypeId {
+        let mut evaluator =
+            AnnotationEvaluator::new(self.module, self.resolution, &mut self.inference.types);
+        let outcome = evaluator.evaluate(annotation, scope);
+        self.errors.add(outcome.errors);
+        outcome.value
+    }
+
+    fn builtin(&mut self, name: &str, args: impl IntoIterator<Item = TypeId>) -> TypeId {
+        let class = Class::builtin(self.resolution, name);
+        self.types().instance(class, args)
+    }
+
+    fn literal(&mut self, literal: Literal) -> TypeId {
+        self.types().intern(Type::Literal(literal))
+    }
+
+    /// Returns the name and the type arguments of `ty` if it is an instance of a builtin class.
+    fn builtin_instance(&self, ty: TypeId) -> Option<(ast::name::Name, Box<[TypeId]>)> {
+        match self.inference.types.get(ty) {
+            Type::Instance { class, args }
+                if self.resolution.symbols().get(class.symbol).kind == SymbolKind::Builtin =>
+            {
+                Some((class.name.clone(), args.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Replaces literal types by the types of their classes, e.g., `Literal[1]` by `int`. This
+    /// is the type of a variable a literal is assigned to.
+    fn widen(&mut self, ty: TypeId) -> TypeId {
+        match self.types().get(ty).clone() {
+            Type::Literal(literal) => {
+                let name = match literal {
+                    Literal::Bool(_) => "bool",
+                    Literal::Int(_) => "int",
+                    Literal::Str(_) => "str",
+                    Literal::Bytes(_) => "bytes",
+                };
+                self.builtin(name, [])
+            }
+            Type::Union(elements) => {
+                let elements: Vec<_> = elements
+                    .iter()
+                    .map(|element| self.widen(*element))
+                    .collect();
+                self.types().union(elements)
+            }
+            Type::Tuple(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.widen(*element))
+                    .collect();
+                self.types().intern(Type::Tuple(elements))
+            }
+            _ => ty,
+        }
+    }
+
+    fn symbol_type(&mut self, id: SymbolId, name: &str) -> TypeId {
+        if let Some(ty) = self.inference.symbol_type(id) {
+            return ty;
+        }
+        match self.resolution.symbols().get(id).kind {
+            SymbolKind::Builtin if name == "object" => {
+                self.types().intern(Type::ClassObject(TypeTable::OBJECT))
+            }
+            SymbolKind::Builtin if is_builtin_class(name) => {
+                let instance = self.builtin(name, []);
+                self.types().intern(Type::ClassObject(instance))
+            }
+            _ => TypeTable::ANY,
+        }
+    }
+
+    /// Adds the type of a value bound to the name at `node`.
+    fn bind_symbol(&mut self, node: ast::NodeIndex, ty: TypeId) {
+        if let Some(id) = self.resolution.symbol_at(node) {
+            let Inference { types, symbols, .. } = &mut self.inference;
+            symbols.insert_inferred(types, id, ty);
+        }
+    }
+
+    fn declare_symbol(&mut self, node: ast::NodeIndex, ty: TypeId) {
+        if let Some(id) = self.resolution.symbol_at(node) {
+            self.inference.symbols.insert_declared(id, ty);
+        }
+    }
+
+    /// Binds the assignment target `target` to a value of type `ty`. Errors about values that are
+    /// not assignable to the declared types of the targets point at `value`.
+    fn bind(&mut self, target: &ast::Expr, ty: TypeId, value: &ast::Expr) {
+        match target {
+            ast::Expr::Name(name) => {
+                self.check_assignment(name, ty, value);
+                let ty = self.widen(ty);
+                self.bind_symbol(name.node_index.load(), ty);
+                self.record(target, ty);
+            }
+            ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+            | ast::Expr::List(ast::ExprList { elts, .. }) => {
+                let types = self.unpack(ty, elts);
+                for (elt, ty) in elts.iter().zip(types) {
+                    self.bind(elt, ty, value);
+                }
+                self.record(target, ty);
+            }
+            ast::Expr::Starred(starred) => {
+                self.bind(&starred.value, ty, value);
+                self.record(target, ty);
+            }
+            // Attributes and subscripts do not bind names.
+            _ => {
+                self.infer(target);
+            }
+        }
+    }
+
+    /// Reports an error if the symbol of `name` has a declared type and `ty` is not assignable to
+    /// it.
+    fn check_assignment(&mut self, name: &ast::ExprName, ty: TypeId, value: &ast::Expr) {
+        let Some(id) = self.resolution.symbol_at(name.node_index.load()) else {
+            return;
+        };
+        let Some(declared) = self.inference.symbols.declared(id) else {
+            return;
+        };
+        if self.is_assignable(ty, declared) {
+            return;
+        }
+        let types = &self.inference.types;
+        let name = name.id.as_str();
+        let message = format!(
+            "a value of type '{}' is not assignable to '{name}' of type '{}'",
+            types.display(ty),
+            types.display(declared),
+        );
+        let mut error = TypeError::new(value.range(), message);
+        // An annotated assignment needs no note pointing at itself.
+        if let Some(decl) = self.resolution.symbols().get(id).decl_node(self.module)
+            && !decl.range().contains_range(value.range())
+        {
+            error = error.with_note(decl.range(), format!("'{name}' is declared here"));
+        }
+        self.errors.add(error);
+    }
+
+    /// Whether a value of type `source` can be used where a value of type `target` is expected.
+    /// `Any` is assignable to and from every type. We know the base classes of the classes in the
+    /// module and of the builtin classes. Classes we know nothing about, e.g., imported ones like
+    /// `typing.Sequence`, might be related to any other type.
+    pub(super) fn is_assignable(&self, source: TypeId, target: TypeId) -> bool {
+        if source == target
+            || matches!(source, TypeTable::ANY | TypeTable::NEVER)
+            || matches!(target, TypeTable::ANY | TypeTable::OBJECT)
+        {
+            return true;
+        }
+        let types = &self.inference.types;
+        match (types.get(source), types.get(target)) {
+            (Type::Union(sources), _) => sources
+                .iter()
+                .all(|source| self.is_assignable(*source, target)),
+            (_, Type::Union(targets)) => targets
+                .iter()
+                .any(|target| self.is_assignable(source, *target)),
+            _ if self.is_unknown(source) || self.is_unknown(target) => true,
+            (Type::Literal(literal), _) => {
+                let name = match literal {
+                    Literal::Bool(_) => "bool",
+                    Literal::Int(_) => "int",
+                    Literal::Str(_) => "str",
+                    Literal::Bytes(_) => "bytes",
+                };
+                self.builtin_instance(target).is_some_and(|(target, args)| {
+                    args.is_empty() && is_promotion(name, target.as_str())
+                })
+            }
+            (
+                Type::Instance { class, args },
+                Type::Instance {
+                    class: target_class,
+                    args: target_args,
+                },
+            ) if class == target_class => {
+                // A class without type arguments stands for any type arguments. Mutable
+                // containers are invariant in their type arguments, but we infer the types of
+                // displays without context, e.g., `[1]` as `list[int]` even where a `list[float]`
+                // is expected. Thus, we treat all type arguments as covariant.
+                target_args.is_empty()
+                    || args
+                        .iter()
+                        .zip(target_args.iter())
+                        .all(|(arg, target_arg)| self.is_assignable(*arg, *target_arg))
+            }
+            (Type::Instance { class, .. }, Type::Instance { .. }) => {
+                self.is_subclass(class, target, &mut Vec::new())
+            }
+            (Type::Tuple(sources), Type::Tuple(targets)) => {
+                sources.len() == targets.len()
+                    && sources
+                        .iter()
+                        .zip(targets.iter())
+                        .all(|(source, target)| self.is_assignable(*source, *target))
+            }
+            (Type::Tuple(sources), Type::VariadicTuple(target)) => sources
+                .iter()
+                .all(|source| self.is_assignable(*source, *target)),
+            (Type::VariadicTuple(source), Type::VariadicTuple(target))
+            | (Type::ClassObject(source), Type::ClassObject(target)) => {
+                self.is_assignable(*source, *target)
+            }
+            // Calling a class creates an instance. We do not know the parameters of `__init__`.
+            (Type::ClassObject(instance), Type::Callable { returns, .. }) => {
+                self.is_assignable(*instance, *returns)
+            }
+            (
+                Type::Callable {
+                    parameters: sources,
+                    returns: source_returns,
+                },
+                Type::Callable {
+                    parameters: targets,
+                    returns: target_returns,
+                },
+            ) => {
+                let parameters = match (sources, targets) {
+                    (Some(sources), Some(targets)) => self.accepts_parameters(sources, targets),
+                    _ => true,
+                };
+                parameters && self.is_assignable(*source_returns, *target_returns)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `ty` is an instance of a class we know nothing about or a type variable, whose
+    /// bound we do not know.
+    fn is_unknown(&self, ty: TypeId) -> bool {
+        match self.inference.types.get(ty) {
+            Type::Instance { class, .. } => !matches!(
+                self.resolution.symbols().get(class.symbol).kind,
+                SymbolKind::Class | SymbolKind::Builtin
+            ),
+            Type::TypeVar { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Whether an instance of `class` is assignable to the instance type `target` of another
+    /// class because of a promotion like `int` to `float` or because `class` is a subclass of the
+    /// class of `target`. `visited` contains the classes whose bases we are looking at, which
+    /// protects us against cycles caused by redefinitions.
+    ///
+    /// A base we know nothing about might be a subclass of any class, except that an imported
+    /// class can't be a subclass of a class defined in the module.
+    fn is_subclass(&self, class: &Class, target: TypeId, visited: &mut Vec<SymbolId>) -> bool {
+        if visited.contains(&class.symbol) {
+            return false;
+        }
+        visited.push(class.symbol);
+        let symbol = self.resolution.symbols().get(class.symbol);
+        if symbol.kind == SymbolKind::Builtin {
+            return self.builtin_instance(target).is_some_and(|(target, _)| {
+                let (name, target) = (class.name.as_str(), target.as_str());
+                is_promotion(name, target) || is_builtin_subclass(name, target)
+            });
+        }
+        let Some(ast::AnyRootNodeRef::Stmt(ast::Stmt::ClassDef(class_def))) =
+            symbol.decl_node(self.module)
+        else {
+            return symbol.kind != SymbolKind::Import || !self.is_module_class(target);
+        };
+        let Some(arguments) = &class_def.arguments else {
+            return false;
+        };
+        let types = &self.inference.types;
+        let target_class = match types.get(target) {
+            Type::Instance { class, .. } => Some(class),
+            _ => None,
+        };
+        arguments.args.iter().any(|base_expr| {
+            let base = self
+                .inference
+                .expression_type(base_expr.node_index().load());
+            let Some(Type::ClassObject(instance)) = base.map(|base| types.get(base)) else {
+                // We do not know what the base is.
+                return !is_imported(self.resolution, base_expr) || !self.is_module_class(target);
+            };
+            match types.get(*instance) {
+                Type::Instance { class, .. } if Some(class) == target_class => {
+                    self.is_assignable(*instance, target)
+                }
+                Type::Instance { class, .. } => self.is_subclass(class, target, visited),
+                Type::Object => false,
+                _ => true,
+            }
+        })
+    }
+
+    /// Whether `ty` is an instance of a class defined in the module.
+    fn is_module_class(&self, ty: TypeId) -> bool {
+        matches!(
+            self.inference.types.get(ty),
+            Type::Instance { class, .. }
+                if self.resolution.symbols().get(class.symbol).kind == SymbolKind::Class
+        )
+    }
+
+    /// Whether a function with the parameters `sources` accepts all calls a function with the
+    /// parameters `targets` accepts. Arguments passed by position are matched by position, the
+    /// ones passed by keyword by name. Parameters are contravariant and the parameters of
+    /// `sources` that no parameter of `targets` is matched with must be optional.
+    fn accepts_parameters(&self, sources: &[Parameter], targets: &[Parameter]) -> bool {
+        use ParameterKind::*;
+
+        fn variadic(parameters: &[Parameter], kind: ParameterKind) -> Option<&Parameter> {
+            parameters.iter().find(|parameter| parameter.kind == kind)
+        }
+        // Matches `sources[source]` with `targets[target]`. A parameter can't receive the
+        // arguments for two parameters, e.g., an argument by position and another one by keyword.
+        fn claim<'p>(
+            sources: &'p [Parameter],
+            matched: &mut [Option<usize>],
+            source: usize,
+            target: usize,
+        ) -> Option<&'p Parameter> {
+            (*matched[source].get_or_insert(target) == target).then_some(&sources[source])
+        }
+
+        let positional: Vec<_> = (0..sources.len())
+            .filter(|index| matches!(sources[*index].kind, PositionalOnly | PositionalOrKeyword))
+            .collect();
+        // The index of the parameter of `targets` each parameter of `sources` is matched with.
+        let mut matched = vec![None; sources.len()];
+        let mut next_position = 0;
+        for (index, target) in targets.iter().enumerate() {
+            // The parameters receiving the arguments for `target`. `None` if there is none.
+            let mut receivers = Vec::new();
+            if matches!(target.kind, PositionalOnly | PositionalOrKeyword) {
+                match positional.get(next_position) {
+                    Some(source) => receivers.push(claim(sources, &mut matched, *source, index)),
+                    // Extra positional arguments go to `*args`.
+                    None => receivers.push(variadic(sources, VarPositional)),
+                }
+                next_position += 1;
+            }
+            if matches!(target.kind, PositionalOrKeyword | KeywordOnly) {
+                let keyword = sources.iter().position(|source| {
+                    matches!(source.kind, PositionalOrKeyword | KeywordOnly)
+                        && target.name.is_some()
+                        && source.name == target.name
+                });
+                match keyword {
+                    Some(source) => receivers.push(claim(sources, &mut matched, source, index)),
+                    // Extra keyword arguments go to `**kwargs`.
+                    None => receivers.push(variadic(sources, VarKeyword)),
+                }
+            }
+            if matches!(target.kind, VarPositional | VarKeyword) {
+                receivers.push(variadic(sources, target.kind));
+            }
+            let accepted = receivers.into_iter().all(|receiver| {
+                receiver.is_some_and(|source| self.is_assignable(target.ty, source.ty))
+            });
+            if !accepted {
+                return false;
+            }
+        }
+        sources.iter().zip(matched).all(|(source, matched)| {
+            matched.is_some()
+                || source.has_default
+                || matches!(source.kind, VarPositional | VarKeyword)
+        })
+    }
+
+    /// Returns the types of the values a value of type `ty` is destructured into by the targets
+    /// `elts`. A starred target gets a list of the values it captures.
+    fn unpack(&mut self, ty: TypeId, elts: &[ast::Expr]) -> Vec<TypeId> {
+        let starred = elts.iter().position(ast::Expr::is_starred_expr);
+        if let Type::Tuple(elements) = self.types().get(ty).clone() {
+            return match starred {
+                None if elements.len() == elts.len() => elements.into_vec(),
+                Some(index) if elements.len() + 1 >= elts.len() => {
+                    let end = index + elements.len() + 1 - elts.len();
+                    let captured: Vec<_> = elements[index..end]
+                        .iter()
+                        .map(|element| self.widen(*element))
+                        .collect();
+                    let captured = self.types().union(captured);
+                    let mut types = elements[..index].to_vec();
+                    types.push(self.builtin("list", [captured]));
+                    types.extend_from_slice(&elements[end..]);
+                    types
+                }
+                // The lengths do not match, which fails at runtime.
+                _ => vec![TypeTable::ANY; elts.len()],
+            };
+        }
+        let element = self.element_type(ty);
+        let mut types = Vec::with_capacity(elts.len());
+        for elt in elts {
+            if elt.is_starred_expr() {
+                let element = self.widen(element);
+                types.push(self.builtin("list", [element]));
+            } else {
+                types.push(element);
+            }
+        }
+        types
+    }
+
+    /// Returns the type of the elements of an iterable of type `ty`.
+    fn element_type(&mut self, ty: TypeId) -> TypeId {
+        match self.types().get(ty).clone() {
+            Type::Tuple(elements) => self.types().union(elements.into_vec()),
+            Type::VariadicTuple(element) => element,
+            Type::Union(elements) => {
+                let elements: Vec<_> = elements
+                    .iter()
+                    .map(|element| self.element_type(*element))
+                    .collect();
+                self.types().union(elements)
+            }
+            Type::Literal(Literal::Str(_)) => self.builtin("str", []),
+            Type::Literal(Literal::Bytes(_)) => self.builtin("int", []),
+            Type::Instance { .. } => match self.builtin_instance(ty) {
+                Some((name, args)) => match (name.as_str(), &*args) {
+                    ("list" | "set" | "frozenset" | "dict", [element, ..]) => *element,
+                    ("str", _) => self.builtin("str", []),
+                    ("bytes" | "bytearray" | "range", _) => self.builtin("int", []),
+                    _ => TypeTable::ANY,
+                },
+                None => TypeTable::ANY,
+            },
+            _ => TypeTable::ANY,
+        }
+    }
+
+    /// Returns the type of the member `name` of the class `class`, if we know it.
+    fn member_type(&mut self, class: &Class, name: &str) -> Option<TypeId> {
+        let symbol = self.resolution.symbols().get(class.symbol);
+        if symbol.kind != SymbolKind::Class {
+            return None;
+        }
+        let scope = self.resolution.scope_created_by(symbol.decl)?;
+        let id = self.resolution.scopes().get(scope).get(name)?;
+        Some(self.symbol_type(id, name))
+    }
+
+    /// Turns the type of a function into the type of a method bound to an instance, i.e., drops
+    /// the first parameter.
+    fn bind_self(&mut self, ty: TypeId) -> TypeId {
+        match self.types().get(ty).clone() {
+            Type::Callable {
+                parameters: Some(parameters),
+                returns,
+            } if parameters.first().is_some_and(|parameter| {
+                matches!(
+                    parameter.kind,
+                    ParameterKind::PositionalOnly | ParameterKind::PositionalOrKeyword
+                )
+            }) =>
+            {
+                let parameters = Some(parameters[1..].into());
+                self.types().intern(Type::Callable { parameters, returns })
+            }
+            _ => ty,
+        }
+    }
+
+    fn infer(&mut self, expr: &ast::Expr) -> TypeId {
+        let ty = match expr {
+            ast::Expr::NoneLiteral(_) => TypeTable::NONE,
+            ast::Expr::BooleanLiteral(literal) => self.literal(Literal::Bool(literal.value)),
+            ast::Expr::NumberLiteral(literal) => match &literal.value {
+                ast::Number::Int(int) => match int.as_i64() {
+                    Some(value) => self.literal(Literal::Int(value)),
+                    None => self.builtin("int", []),
+                },
+                ast::Number::Float(_) => self.builtin("float", []),
+                ast::Number::Complex { .. } => self.builtin("complex", []),
+            },
+            ast::Expr::StringLiteral(literal) => {
+                self.literal(Literal::Str(literal.value.to_str().into()))
+            }
+            ast::Expr::BytesLiteral(literal) => {
+                self.literal(Literal::Bytes(literal.value.bytes().collect()))
+            }
+            ast::Expr::FString(_) => {
+                walk_expr(self, expr);
+                self.builtin("str", [])
+            }
+            ast::Expr::Name(name) => match self.resolution.symbol_at(name.node_index.load()) {
+                Some(id) => self.symbol_type(id, name.id.as_str()),
+                None => TypeTable::ANY,
+            },
+            ast::Expr::List(list) => {
+                let element = self.elements_type(&list.elts);
+                self.builtin("list", [element])
+            }
+            ast::Expr::Set(set) => {
+                let element = self.elements_type(&set.elts);
+                self.builtin("set", [element])
+            }
+            ast::Expr::Tuple(tuple) => {
+                if tuple.elts.iter().any(ast::Expr::is_starred_expr) {
+                    let element = self.elements_type(&tuple.elts);
+                    self.types().intern(Type::VariadicTuple(element))
+                } else {
+                    let elements = tuple.elts.iter().map(|elt| self.infer(elt)).collect();
+                    self.types().intern(Type::Tuple(elements))
+                }
+            }
+            ast::Expr::Dict(dict) => self.dict_type(dict),
+            ast::Expr::Attribute(attribute) => {
+                let value = self.infer(&attribute.value);
+                self.attribute_type(value, attribute.attr.as_str())
+            }
+            ast::Expr::Subscript(subscript) => {
+                let value = self.infer(&subscript.value);
+                let index = self.infer(&subscript.slice);
+                self.subscript_type(expr, subscript, value, index)
+            }
+            ast::Expr::Call(call) => {
+                let callee = self.infer(&call.func);
+                self.visit_arguments(&call.arguments);
+                self.call_type(callee)
+            }
+            ast::Expr::BinOp(binop) => {
+                let left = self.infer(&binop.left);
+                let right = self.infer(&binop.right);
+                self.binary_type(binop.op, left, right)
+            }
+            ast::Expr::UnaryOp(unary) => {
+                let operand = self.infer(&unary.operand);
+                self.unary_type(unary.op, operand)
+            }
+            // The result is one of the operands.
+            ast::Expr::BoolOp(boolop) => {
+                let values: Vec<_> = boolop
+                    .values
+                    .iter()
+                    .map(|value| self.infer(value))
+                    .collect();
+                self.types().union(values)
+            }
+            ast::Expr::Compare(_) => {
+                walk_expr(self, expr);
+                self.builtin("bool", [])
+            }
+            ast::Expr::If(if_expr) => {
+                self.infer(&if_expr.test);
+                let body = self.infer(&if_expr.body);
+                let orelse = self.infer(&if_expr.orelse);
+                self.types().union([body, orelse])
+            }
+            ast::Expr::Named(named) => {
+                let value = self.infer(&named.value);
+                self.bind(&named.target, value, &named.value);
+                value
+            }
+            ast::Expr::Lambda(lambda) => self.lambda_type(lambda),
+            ast::Expr::ListComp(comp) => self.comprehension_type(expr, &comp.generators, |this| {
+                let element = this.infer(&comp.elt);
+                let element = this.widen(element);
+                this.builtin("list", [element])
+            }),
+            ast::Expr::SetComp(comp) => self.comprehension_type(expr, &comp.generators, |this| {
+                let element = this.infer(&comp.elt);
+                let element = this.widen(element);
+                this.builtin("set", [element])
+            }),
+            ast::Expr::DictComp(comp) => self.comprehension_type(expr, &comp.generators, |this| {
+                let key = this.infer(&comp.key);
+                let key = this.widen(key);
+                let value = this.infer(&comp.value);
+                let value = this.widen(value);
+                this.builtin("dict", [key, value])
+            }),
+            // `Generator` is not a builtin, so we cannot express the type of a generator.
+            ast::Expr::Generator(generator) => {
+                self.comprehension_type(expr, &generator.generators, |this| {
+                    this.infer(&generator.elt);
+                    TypeTable::ANY
+                })
+            }
+            ast::Expr::Starred(starred) => self.infer(&starred.value),
+            ast::Expr::Slice(_) => {
+                walk_expr(self, expr);
+                self.builtin("slice", [])
+            }
+            ast::Expr::TString(_)
+            | ast::Expr::EllipsisLiteral(_)
+            | ast::Expr::Await(_)
+            | ast::Expr::Yield(_)
+            | ast::Expr::YieldFrom(_)
+            | ast::Expr::IpyEscapeCommand(_) => {
+                walk_expr(self, expr);
+                TypeTable::ANY
+            }
+        };
+        self.record(expr, ty)
+    }
+
+    /// Returns the union of the types of the elements of a list, set or tuple display, including
+    /// the elements of starred iterables.
+    fn elements_type(&mut self, elts: &[ast::Expr]) -> TypeId {
+        if elts.is_empty() {
+            return TypeTable::ANY;
+        }
+        let mut elements = Vec::with_capacity(elts.len());
+        for elt in elts {
+            let ty = self.infer(elt);
+            let ty = if elt.is_starred_expr() {
+                self.element_type(ty)
+            } else {
+                ty
+            };
+            elements.push(self.widen(ty));
+        }
+        self.types().union(elements)
+    }
+
+    fn dict_type(&mut self, dict: &ast::ExprDict) -> TypeId {
+        if dict.items.is_empty() {
+            return self.builtin("dict", [TypeTable::ANY, TypeTable::ANY]);
+        }
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+        for item in &dict.items {
+            let value = self.infer(&item.value);
+            match &item.key {
+                Some(key) => {
+                    let key = self.infer(key);
+                    keys.push(self.widen(key));
+                    values.push(self.widen(value));
+                }
+                // A `**mapping` item.
+                None => match self.builtin_instance(value) {
+                    Some((name, args)) if name.as_str() == "dict" && args.len() == 2 => {
+                        keys.push(args[0]);
+                        values.push(args[1]);
+                    }
+                    _ => {
+                        keys.push(TypeTable::ANY);
+                        values.push(TypeTable::ANY);
+                    }
+                },
+            }
+        }
+        let key = self.types().union(keys);
+        let value = self.types().union(values);
+        self.builtin("dict", [key, value])
+    }
+
+    fn attribute_type(&mut self, value: TypeId, name: &str) -> TypeId {
+        match self.types().get(value).clone() {
+            Type::Instance { class, .. } => match self.member_type(&class, name) {
+                Some(ty) => self.bind_self(ty),
+                None => TypeTable::ANY,
+            },
+            Type::ClassObject(instance) => match self.types().get(instance).clone() {
+                Type::Instance { class, .. } => {
+                    self.member_type(&class, name).unwrap_or(TypeTable::ANY)
+                }
+                _ => TypeTable::ANY,
+            },
+            Type::Union(elements) => {
+                let elements: Vec<_> = elements
+                    .iter()
+                    .map(|element| self.attribute_type(*element, name))
+                    .collect();
+                self.types().union(elements)
+            }
+            _ => TypeTable::ANY,
+        }
+    }
+
+    fn subscript_type(
+        &mut self,
+        expr: &ast::Expr,
+        subscript: &ast::ExprSubscript,
+        value: TypeId,
+        index: TypeId,
+    ) -> TypeId {
+        let is_slice = subscript.slice.is_slice_expr();
+        let value = self.widen(value);
+        match self.types().get(value).clone() {
+            Type::Tuple(elements) if is_slice => {
+                let element = self.types().union(elements.into_vec());
+                self.types().intern(Type::VariadicTuple(element))
+            }
+            Type::Tuple(elements) => match self.types().get(index) {
+                Type::Literal(Literal::Int(index)) => {
+                    let index = if *index < 0 {
+                        *index + elements.len() as i64
+                    } else {
+                        *index
+                    };
+                    usize::try_from(index)
+                        .ok()
+                        .and_then(|index| elements.get(index).copied())
+                        .unwrap_or(TypeTable::ANY)
+                }
+                _ => self.types().union(elements.into_vec()),
+            },
+            Type::VariadicTuple(_) if is_slice => value,
+            Type::VariadicTuple(element) => element,
+            // A generic alias like `list[int]`, e.g., in `list[int]()`.
+            Type::ClassObject(_) => {
+                let mut evaluator = AnnotationEvaluator::new(
+                    self.module,
+                    self.resolution,
+                    &mut self.inference.types,
+                );
+                let instance = evaluator.evaluate(expr, self.scope).value;
+                self.types().intern(Type::ClassObject(instance))
+            }
+            Type::Instance { .. } => match self.builtin_instance(value) {
+                Some((name, args)) => match (name.as_str(), &*args) {
+                    ("list", _) if is_slice => value,
+                    ("list", [element]) => *element,
+                    ("dict", [_, value]) => *value,
+                    ("str", _) => value,
+                    ("bytes" | "bytearray", _) if is_slice => value,
+                    ("bytes" | "bytearray", _) => self.builtin("int", []),
+                    _ => TypeTable::ANY,
+                },
+                None => TypeTable::ANY,
+            },
+            _ => TypeTable::ANY,
+        }
+    }
+
+    fn call_type(&mut self, callee: TypeId) -> TypeId {
+        match self.types().get(callee).clone() {
+            Type::Callable { returns, .. } => returns,
+            // Calling a class creates an instance.
+            Type::ClassObject(instance) => instance,
+            Type::Union(elements) => {
+                let elements: Vec<_> = elements
+                    .iter()
+                    .map(|element| self.call_type(*element))
+                    .collect();
+                self.types().union(elements)
+            }
+            _ => TypeTable::ANY,
+        }
+    }
+
+    fn binary_type(&mut self, op: ast::Operator, left: TypeId, right: TypeId) -> TypeId {
+        use ast::Operator::*;
+        let left = self.widen(left);
+        let right = self.widen(right);
+        for (union, other, is_left) in [(left, right, true), (right, left, false)] {
+            if let Type::Union(elements) = self.types().get(union).clone() {
+                let results: Vec<_> = elements
+                    .iter()
+                    .map(|&element| {
+                        if is_left {
+                            self.binary_type(op, element, other)
+                        } else {
+                            self.binary_type(op, other, element)
+                        }
+                    })
+                    .collect();
+                return self.types().union(results);
+            }
+        }
+        let (Some((left_name, left_args)), Some((right_name, right_args))) =
+            (self.builtin_instance(left), self.builtin_instance(right))
+        else {
+            return TypeTable::ANY;
+        };
+        let name = match (left_name.as_str(), op, right_name.as_str()) {
+            ("list", Add, "list") => {
+                let element = self
+                    .types()
+                    .union(left_args.iter().chain(&right_args).copied());
+                return self.builtin("list", [element]);
+            }
+            ("list" | "str" | "bytes", Mult, "int" | "bool") => return left,
+            ("int" | "bool", Mult, "list" | "str" | "bytes") => return right,
+            ("str", Add, "str") | ("str", Mod, _) => "str",
+            ("bytes", Add, "bytes") | ("bytes", Mod, _) => "bytes",
+            ("int" | "bool", Div, "int" | "bool") => "float",
+            ("bool", BitAnd | BitOr | BitXor, "bool") => "bool",
+            (
+                "int" | "bool",
+                Add | Sub | Mult | FloorDiv | Mod | Pow | LShift | RShift | BitAnd | BitOr | BitXor,
+                "int" | "bool",
+            ) => "int",
+            (
+                "int" | "bool" | "float",
+                Add | Sub | Mult | Div | FloorDiv | Mod | Pow,
+                "int" | "bool" | "float",
+            ) => "float",
+            _ => return TypeTable::ANY,
+        };
+        self.builtin(name, [])
+    }
+
+    fn unary_type(&mut self, op: ast::UnaryOp, operand: TypeId) -> TypeId {
+        if op == ast::UnaryOp::Not {
+            return self.builtin("bool", []);
+        }
+        if op == ast::UnaryOp::USub
+            && let Type::Literal(Literal::Int(value)) = self.types().get(operand)
+            && let Some(value) = value.checked_neg()
+        {
+            return self.literal(Literal::Int(value));
+        }
+        let operand = self.widen(operand);
+        match self.builtin_instance(operand) {
+            Some((name, _)) => match (op, name.as_str()) {
+                (_, "int" | "bool") => self.builtin("int", []),
+                (ast::UnaryOp::UAdd | ast::UnaryOp::USub, "float" | "complex") => operand,
+                _ => TypeTable::ANY,
+            },
+            None => TypeTable::ANY,
+        }
+    }
+
+    fn lambda_type(&mut self, lambda: &ast::ExprLambda) -> TypeId {
+        let mut parameters = Vec::new();
+        if let Some(lambda_parameters) = &lambda.parameters {
+            for (parameter, kind, default) in all_parameters(lambda_parameters) {
+                if let Some(default) = default {
+                    self.infer(default);
+                }
+                parameters.push(Parameter {
+                    kind,
+                    name: Some(parameter.name.id.clone()),
+                    ty: TypeTable::ANY,
+                    has_default: default.is_some(),
+                });
+            }
+        }
+        let scope = self.resolution.scope_created_by(lambda.node_index.load());
+        let scope = std::mem::replace(&mut self.scope, scope.unwrap_or(self.scope));
+        let class = self.class.take();
+        let returns = self.infer(&lambda.body);
+        self.scope = scope;
+        self.class = class;
+        let parameters = Some(parameters.into_boxed_slice());
+        self.types().intern(Type::Callable { parameters, returns })
+    }
+
+    /// Binds the targets of the generators of a comprehension and returns the type of the
+    /// comprehension, which `element` computes.
+    fn comprehension_type(
+        &mut self,
+        expr: &ast::Expr,
+        generators: &[ast::Comprehension],
+        element: impl FnOnce(&mut Self) -> TypeId,
+    ) -> TypeId {
+        let scope = self.resolution.scope_created_by(expr.node_index().load());
+        let scope = std::mem::replace(&mut self.scope, scope.unwrap_or(self.scope));
+        let class = self.class.take();
+        for generator in generators {
+            let iter = self.infer(&generator.iter);
+            let element = if generator.is_async {
+                TypeTable::ANY
+            } else {
+                self.element_type(iter)
+            };
+            self.bind(&generator.target, element, &generator.iter);
+            for condition in &generator.ifs {
+                self.infer(condition);
+            }
+        }
+        let ty = element(self);
+        self.scope = scope;
+        self.class = class;
+        ty
+    }
+
+    /// Returns the type of an exception caught by an `except` clause for the exception classes of
+    /// type `ty`.
+    fn exception_type(&mut self, ty: TypeId) -> TypeId {
+        match self.types().get(ty).clone() {
+            Type::ClassObject(instance) => instance,
+            Type::Tuple(elements) => {
+                let elements: Vec<_> = elements
+                    .iter()
+                    .map(|element| self.exception_type(*element))
+                    .collect();
+                self.types().union(elements)
+            }
+            _ => TypeTable::ANY,
+        }
+    }
+
+    /// Whether `expr` refers to the builtin `name`.
+    fn is_builtin(&self, expr: &ast::Expr, name: &str) -> bool {
+        let ast::Expr::Name(expr_name) = expr else {
+            return false;
+        };
+        expr_name.id.as_str() == name
+            && self
+                .resolution
+                .symbol_at(expr_name.node_index.load())
+                .is_some_and(|id| self.resolution.symbols().get(id).kind == SymbolKind::Builtin)
+    }
+
+    fn visit_function(&mut self, func_def: &ast::StmtFunctionDef) {
+        for decorator in &func_def.decorator_list {
+            self.infer(&decorator.expression);
+        }
+        let decorated = |name| {
+            func_def
+                .decorator_list
+                .iter()
+                .any(|decorator| self.is_builtin(&decorator.expression, name))
+        };
+        let is_static = decorated("staticmethod");
+        let is_class = decorated("classmethod") || func_def.name.as_str() == "__new__";
+        // The implicit type of the first parameter of a method.
+        let receiver = match self.class {
+            Some(_) if is_static => None,
+            Some(class) if is_class => Some(self.types().intern(Type::ClassObject(class))),
+            class => class,
+        };
+        // The annotations are evaluated in the annotation scope of the type parameters, if there
+        // are any.
+        let annotation_scope = func_def
+            .type_params
+            .as_deref()
+            .and_then(|type_params| {
+                self.resolution
+                    .scope_created_by(type_params.node_index.load())
+            })
+            .unwrap_or(self.scope);
+        let mut parameters = Vec::new();
+        for (index, (parameter, kind, default)) in all_parameters(&func_def.parameters).enumerate()
+        {
+            if let Some(default) = default {
+                self.infer(default);
+            }
+            let ty = match (&parameter.annotation, receiver) {
+                (Some(annotation), _) => self.annotation(annotation, annotation_scope),
+                (None, Some(receiver))
+                    if index == 0
+                        && matches!(
+                            kind,
+                            ParameterKind::PositionalOnly | ParameterKind::PositionalOrKeyword
+                        ) =>
+                {
+                    receiver
+                }
+                (None, _) => TypeTable::ANY,
+            };
+            // The type of the parameter in the body.
+            let declared = match kind {
+                ParameterKind::VarPositional => self.types().intern(Type::VariadicTuple(ty)),
+                ParameterKind::VarKeyword => {
+                    let str = self.builtin("str", []);
+                    self.builtin("dict", [str, ty])
+                }
+                _ => ty,
+            };
+            self.declare_symbol(parameter.name.node_index.load(), declared);
+            parameters.push(Parameter {
+                kind,
+                name: Some(parameter.name.id.clone()),
+                ty,
+                has_default: default.is_some(),
+            });
+        }
+        let returns = match &func_def.returns {
+            Some(returns) => self.annotation(returns, annotation_scope),
+            None => TypeTable::ANY,
+        };
+        // We do not know what decorators return.
+        let ty = if func_def.decorator_list.is_empty() {
+            let parameters = Some(parameters.into_boxed_slice());
+            self.types().intern(Type::Callable { parameters, returns })
+        } else {
+            TypeTable::ANY
+        };
+        // The name is bound before the body is executed, which might call the function.
+        self.bind_symbol(func_def.name.node_index.load(), ty);
+
+        let scope = self.resolution.scope_created_by(func_def.node_index.load());
+        let scope = std::mem::replace(&mut self.scope, scope.unwrap_or(self.scope));
+        let class = self.class.take();
+        self.visit_body(&func_def.body);
+        self.scope = scope;
+        self.class = class;
+    }
+
+    fn visit_class(&mut self, class_def: &ast::StmtClassDef) {
+        for decorator in &class_def.decorator_list {
+            self.infer(&decorator.expression);
+        }
+        if let Some(arguments) = &class_def.arguments {
+            self.visit_arguments(arguments);
+        }
+        let node = class_def.name.node_index.load();
+        let instance = match self.resolution.symbol_at(node) {
+            Some(symbol) => {
+                let class = Class {
+                    symbol,
+                    name: class_def.name.id.clone(),
+                };
+                self.types().instance(class, [])
+            }
+            None => TypeTable::ANY,
+        };
+        let ty = self.types().intern(Type::ClassObject(instance));
+        self.bind_symbol(node, ty);
+
+        let scope = self
+            .resolution
+            .scope_created_by(class_def.node_index.load());
+        let scope = std::mem::replace(&mut self.scope, scope.unwrap_or(self.scope));
+        let class = self.class.replace(instance);
+        self.visit_body(&class_def.body);
+        self.scope = scope;
+        self.class = class;
+    }
+}
+
+impl<'m> SourceOrderVisitor<'m> for Inferrer<'_> {
+    fn visit_stmt(&mut self, stmt: &'m ast::Stmt) {
+        match stmt {
+            ast::Stmt::FunctionDef(func_def) => self.visit_function(func_def),
+            ast::Stmt::ClassDef(class_def) => self.visit_class(class_def),
+            ast::Stmt::Assign(assign) => {
+                let value = self.infer(&assign.value);
+                for target in &assign.targets {
+                    self.bind(target, value, &assign.value);
+                }
+            }
+            ast::Stmt::AnnAssign(assign) => {
+                let declared = self.annotation(&assign.annotation, self.scope);
+                if let ast::Expr::Name(name) = &*assign.target {
+                    self.declare_symbol(name.node_index.load(), declared);
+                }
+                match &assign.value {
+                    Some(value) => {
+                        let ty = self.infer(value);
+                        self.bind(&assign.target, ty, value);
+                    }
+                    // A bare annotation does not bind the name.
+                    None if assign.target.is_name_expr() => {
+                        self.record(&assign.target, declared);
+                    }
+                    None => self.bind(&assign.target, declared, &assign.target),
+                }
+            }
+            ast::Stmt::AugAssign(assign) => {
+                let target = self.infer(&assign.target);
+                let value = self.infer(&assign.value);
+                let result = self.binary_type(assign.op, target, value);
+                self.bind(&assign.target, result, &assign.value);
+            }
+            ast::Stmt::For(for_stmt) => {
+                let iter = self.infer(&for_stmt.iter);
+                let element = if for_stmt.is_async {
+                    TypeTable::ANY
+                } else {
+                    self.element_type(iter)
+                };
+                self.bind(&for_stmt.target, element, &for_stmt.iter);
+                self.visit_body(&for_stmt.body);
+                self.visit_body(&for_stmt.orelse);
+            }
+            // We do not know what `__enter__` returns.
+            ast::Stmt::With(with) => {
+                for item in &with.items {
+                    self.infer(&item.context_expr);
+                    if let Some(target) = &item.optional_vars {
+                        self.bind(target, TypeTable::ANY, &item.context_expr);
+                    }
+                }
+                self.visit_body(&with.body);
+            }
+            ast::Stmt::Import(import) => {
+                for alias in &import.names {
+                    let (node, module) = match &alias.asname {
+                        Some(asname) => (asname.node_index.load(), alias.name.id.as_str()),
+                        // `import a.b.c` binds the top-level package `a`.
+                        None => {
+                            let module = alias.name.id.as_str();
+                            let package = module
+                                .split_once('.')
+                                .map_or(module, |(package, _)| package);
+                            (alias.name.node_index.load(), package)
+                        }
+                    };
+                    let ty = self.types().intern(Type::Module(module.into()));
+                    self.bind_symbol(node, ty);
+                }
+            }
+            // The value of a type alias is an annotation.
+            ast::Stmt::TypeAlias(_) => {}
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'m ast::Expr) {
+        self.infer(expr);
+    }
+
+    fn visit_annotation(&mut self, _: &'m ast::Expr) {}
+
+    fn visit_except_handler(&mut self, handler: &'m ast::ExceptHandler) {
+        let ast::ExceptHandler::ExceptHandler(handler) = handler;
+        let ty = match &handler.type_ {
+            Some(type_) => {
+                let ty = self.infer(type_);
+                self.exception_type(ty)
+            }
+            None => TypeTable::ANY,
+        };
+        if let Some(name) = &handler.name {
+            self.bind_symbol(name.node_index.load(), ty);
+        }
+        self.visit_body(&handler.body);
+    }
+}
+
+/// Whether an instance of the builtin class `source` is assignable to the builtin class `target`,
+/// e.g., an `int` to a `float`.
+fn is_promotion(source: &str, target: &str) -> bool {
+    source == target
+        || matches!(
+            (source, target),
+            ("bool", "int" | "float" | "complex")
+                | ("int", "float" | "complex")
+                | ("float", "complex")
+        )
+}
+
+/// Whether the expression `expr` refers to something imported, e.g., `Sequence[int]` after
+/// `from typing import Sequence` or `abc.ABC` after `import abc`.
+fn is_imported(resolution: &Resolution, expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Name(name) => resolution
+            .symbol_at(name.node_index.load())
+            .is_some_and(|id| resolution.symbols().get(id).kind == SymbolKind::Import),
+        ast::Expr::Attribute(ast::ExprAttribute { value, .. })
+        | ast::Expr::Subscript(ast::ExprSubscript { value, .. }) => is_imported(resolution, value),
+        _ => false,
+    }
+}
+
+/// Returns the parameters in source order together with their kinds and default values.
+fn all_parameters(
+    parameters: &ast::Parameters,
+) -> impl Iterator<Item = (&ast::Parameter, ParameterKind, Option<&ast::Expr>)> {
+    fn with_defaults(
+        parameters: &[ast::ParameterWithDefault],
+        kind: ParameterKind,
+    ) -> impl Iterator<Item = (&ast::Parameter, ParameterKind, Option<&ast::Expr>)> {
+        parameters
+            .iter()
+            .map(move |parameter| (&parameter.parameter, kind, parameter.default.as_deref()))
+    }
+
+    let ast::Parameters {
+        posonlyargs,
+        args,
+        vararg,
+        kwonlyargs,
+        kwarg,
+        ..
+    } = parameters;
+    with_defaults(posonlyargs, ParameterKind::PositionalOnly)
+        .chain(with_defaults(args, ParameterKind::PositionalOrKeyword))
+        .chain(
+            vararg
+                .as_deref()
+                .map(|parameter| (parameter, ParameterKind::VarPositional, None)),
+        )
+        .chain(with_defaults(kwonlyargs, ParameterKind::KeywordOnly))
+        .chain(
+            kwarg
+                .as_deref()
+                .map(|parameter| (parameter, ParameterKind::VarKeyword, None)),
+        )
+}
@@ -0,0 +1,773 @@
+use ast::HasNodeIndex;
+use ast::visitor::source_order::{SourceOrderVisitor, walk_expr};
+use text_size::Ranged;
+
+use super::*;
+use crate::indexed::IndexedModule;
+use crate::resolver::Resolver;
+use crate::scope::ScopeTable;
+use crate::symbol::{Symbol, SymbolTable, no_node_index};
+
+/// A type table together with the builtin classes the tests need.
+struct Fixture {
+    types: TypeTable,
+    symbols: SymbolTable,
+    scopes: ScopeTable,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        Self {
+            types: TypeTable::new(),
+            symbols: SymbolTable::new(),
+            scopes: ScopeTable::new(no_node_index()),
+        }
+    }
+
+    fn class(&mut self, name: &str) -> Class {
+        let symbol = self
+            .symbols
+            .insert(Symbol::builtin(self.scopes.builtins_id()));
+        Class { symbol, name: name.into() }
+    }
+
+    fn instance(&mut self, name: &str, args: &[TypeId]) -> TypeId {
+        let class = self.class(name);
+        self.types.instance(class, args.iter().copied())
+    }
+
+    fn show(&self, id: TypeId) -> String {
+        self.types.display(id).to_string()
+    }
+}
+
+fn parameter(kind: ParameterKind, name: Option<&str>, ty: TypeId, has_default: bool) -> Parameter {
+    Parameter {
+        kind,
+        name: name.map(Into::into),
+        ty,
+        has_default,
+    }
+}
+
+#[test]
+fn equal_types_are_interned_once() {
+    let mut f = Fixture::new();
+    let int = f.class("int");
+    let a = f.types.instance(int.clone(), []);
+    let b = f.types.instance(int, []);
+    assert_eq!(a, b);
+    assert_eq!(f.types.intern(Type::Any), TypeTable::ANY);
+    assert_eq!(f.types.intern(Type::None), TypeTable::NONE);
+}
+
+#[test]
+fn unions_are_flattened() {
+    let mut f = Fixture::new();
+    let int = f.instance("int", &[]);
+    let str = f.instance("str", &[]);
+    let inner = f.types.union([int, TypeTable::NONE]);
+    let outer = f.types.union([str, inner, int, TypeTable::NEVER]);
+    assert_eq!(f.show(outer), "str | int | None");
+    assert_eq!(f.types.union([int, int]), int);
+    assert_eq!(f.types.union([]), TypeTable::NEVER);
+}
+
+#[test]
+fn display_in_typing_syntax() {
+    let mut f = Fixture::new();
+    let int = f.instance("int", &[]);
+    let str = f.instance("str", &[]);
+    let list = f.instance("list", &[int]);
+    let dict = f.instance("dict", &[str, list]);
+    assert_eq!(f.show(dict), "dict[str, list[int]]");
+    let class = f.types.intern(Type::ClassObject(list));
+    assert_eq!(f.show(class), "type[list[int]]");
+    let tuple = f.types.intern(Type::Tuple(Box::new([int, str])));
+    assert_eq!(f.show(tuple), "tuple[int, str]");
+    let empty = f.types.intern(Type::Tuple(Box::new([])));
+    assert_eq!(f.show(empty), "tuple[()]");
+    let variadic = f.types.intern(Type::VariadicTuple(int));
+    assert_eq!(f.show(variadic), "tuple[int, ...]");
+    let module = f.types.intern(Type::Module("os.path".into()));
+    assert_eq!(f.show(module), "Module(\"os.path\")");
+    let symbol = f.class("T").symbol;
+    let var = f.types.intern(Type::TypeVar { symbol, name: "T".into() });
+    assert_eq!(f.show(var), "T");
+    for (id, expected) in [
+        (TypeTable::ANY, "Any"),
+        (TypeTable::NEVER, "Never"),
+        (TypeTable::NONE, "None"),
+        (TypeTable::OBJECT, "object"),
+    ] {
+        assert_eq!(f.show(id), expected);
+    }
+}
+
+#[test]
+fn display_literals() {
+    let mut f = Fixture::new();
+    for (literal, expected) in [
+        (Literal::Bool(true), "Literal[True]"),
+        (Literal::Int(-42), "Literal[-42]"),
+        (Literal::Str("it's\n".into()), "Literal['it\\'s\\n']"),
+        (
+            Literal::Bytes(b"a\x00'".to_vec().into()),
+            "Literal[b'a\\x00\\'']",
+        ),
+    ] {
+        let id = f.types.intern(Type::Literal(literal));
+        assert_eq!(f.show(id), expected);
+    }
+}
+
+#[test]
+fn display_callables() {
+    let mut f = Fixture::new();
+    let int = f.instance("int", &[]);
+    let str = f.instance("str", &[]);
+
+    let gradual = f
+        .types
+        .intern(Type::Callable { parameters: None, returns: int });
+    assert_eq!(f.show(gradual), "Callable[..., int]");
+
+    let simple = f.types.intern(Type::Callable {
+        parameters: Some(Box::new([
+            parameter(ParameterKind::PositionalOnly, None, int, false),
+            parameter(ParameterKind::PositionalOnly, None, str, false),
+        ])),
+        returns: TypeTable::NONE,
+    });
+    assert_eq!(f.show(simple), "Callable[[int, str], None]");
+
+    let full = f.types.intern(Type::Callable {
+        parameters: Some(Box::new([
+            parameter(ParameterKind::PositionalOnly, Some("a"), int, false),
+            parameter(ParameterKind::PositionalOrKeyword, Some("b"), str, true),
+            parameter(ParameterKind::KeywordOnly, Some("c"), int, false),
+            parameter(ParameterKind::VarKeyword, Some("kwargs"), str, false),
+        ])),
+        returns: int,
+    });
+    assert_eq!(
+        f.show(full),
+        "(a: int, /, b: str = ..., *, c: int, **kwargs: str) -> int"
+    );
+
+    let variadic = f.types.intern(Type::Callable {
+        parameters: Some(Box::new([
+            parameter(ParameterKind::VarPositional, Some("args"), int, false),
+            parameter(ParameterKind::KeywordOnly, Some("key"), str, true),
+        ])),
+        returns: int,
+    });
+    assert_eq!(f.show(variadic), "(*args: int, key: str = ...) -> int");
+
+    let optional = f.types.union([full, TypeTable::NONE]);
+    assert_eq!(
+        f.show(optional),
+        "((a: int, /, b: str = ..., *, c: int, **kwargs: str) -> int) | None"
+    );
+}
+
+#[test]
+fn symbol_types() {
+    let mut f = Fixture::new();
+    let int = f.instance("int", &[]);
+    let str = f.instance("str", &[]);
+    let x = f.class("x").symbol;
+    let mut symbols = SymbolTypes::default();
+    assert_eq!(symbols.declared(x), None);
+    symbols.insert_declared(x, int);
+    symbols.insert_declared(x, str);
+    assert_eq!(symbols.declared(x), Some(int));
+    symbols.insert_inferred(&mut f.types, x, int);
+    symbols.insert_inferred(&mut f.types, x, str);
+    let inferred = symbols.inferred(x).unwrap();
+    assert_eq!(f.show(inferred), "int | str");
+}
+
+/// Evaluates the annotations of the module-level annotated assignments in `source`. Renders each
+/// of them as its type or, if it is invalid, as its errors together with the text they point at.
+fn annotations(source: &str) -> Vec<String> {
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let resolution = Resolver::new(module.syntax()).run().value;
+    let root_id = resolution.scopes().root_id();
+    let mut types = TypeTable::new();
+    let mut evaluator = AnnotationEvaluator::new(&module, &resolution, &mut types);
+    let mut results = Vec::new();
+    for stmt in &module.syntax().body {
+        let ast::Stmt::AnnAssign(assign) = stmt else {
+            continue;
+        };
+        let outcome = evaluator.evaluate(&assign.annotation, root_id);
+        let errors: Vec<_> = outcome
+            .errors
+            .into_iter()
+            .map(|error| format!("{} at {:?}", error.message, &source[error.range]))
+            .collect();
+        if errors.is_empty() {
+            results.push(evaluator.types().display(outcome.value).to_string());
+        } else {
+            results.push(errors.join("; "));
+        }
+    }
+    results
+}
+
+#[test]
+fn annotations_are_lowered_into_types() {
+    let source = r#"
+import collections.abc
+import typing as t
+from typing import Callable, Literal, Optional, ParamSpec, TypeVar, Union
+
+class A:
+    class B: ...
+
+T = TypeVar("T")
+P = ParamSpec("P")
+IntList = list[int]
+type Pair[U] = tuple[U, U]
+
+a: int
+b: int | None
+c: list[int]
+d: Optional[str]
+e: Union[int, str, None]
+f: Callable[[int, str], bool]
+g: Callable[..., None]
+h: Literal[1, -2, "x", b"y", True, None]
+i: "A"
+j: list["A.B"]
+k: None
+l: t.Optional[A.B]
+m: collections.abc.Callable[P, T]
+n: IntList
+o: tuple[int, ...]
+p: tuple[()]
+q: dict[str, "list[T]"]
+r: type[A]
+s: Pair
+"#;
+    assert_eq!(
+        annotations(source),
+        &[
+            "int",
+            "int | None",
+            "list[int]",
+            "str | None",
+            "int | str | None",
+            "Callable[[int, str], bool]",
+            "Callable[..., None]",
+            "Literal[1] | Literal[-2] | Literal['x'] | Literal[b'y'] | Literal[True] | None",
+            "A",
+            "list[B]",
+            "None",
+            "B | None",
+            "Callable[..., T]",
+            "list[int]",
+            "tuple[int, ...]",
+            "tuple[()]",
+            "dict[str, list[T]]",
+            "type[A]",
+            "tuple[U, U]",
+        ]
+    );
+}
+
+#[test]
+fn invalid_type_expressions_are_reported() {
+    let source = r#"
+import os
+from typing import Callable, Literal, Optional
+
+def f(): ...
+
+x = 1
+
+a: f()
+b: 1 + 2
+c: undefined
+d: os
+e: f
+g: x
+h: Optional
+i: Literal[len]
+j: "1 +"
+k: Callable[int, str]
+l: list["undefined"]
+m: len
+n: Optional[int, str]
+"#;
+    assert_eq!(
+        annotations(source),
+        &[
+            "call expression is not allowed in a type expression at \"f()\"",
+            "binary operation is not allowed in a type expression at \"1 + 2\"",
+            // The resolver reports undefined names outside of forward references.
+            "Any",
+            "module 'os' is not allowed in a type expression at \"os\"",
+            "function 'f' is not allowed in a type expression at \"f\"",
+            "variable 'x' is not allowed in a type expression at \"x\"",
+            "'Optional' requires type arguments at \"Optional\"",
+            "name is not allowed in a 'Literal' type at \"len\"",
+            "forward reference is not a valid expression at \"\\\"1 +\\\"\"",
+            "'Callable' expects a list of parameter types, '...' or a parameter specification \
+             at \"int\"",
+            "name 'undefined' is not defined at \"\\\"undefined\\\"\"",
+            "builtin 'len' is not allowed in a type expression at \"len\"",
+            "'Optional' expects a single type argument at \"Optional[int, str]\"",
+        ]
+    );
+}
+
+#[test]
+fn forward_references_to_star_imports_are_not_reported() {
+    let source = r#"
+from collections.abc import *
+a: "Sequence[int]"
+"#;
+    assert_eq!(annotations(source), &["Any"]);
+}
+
+#[test]
+fn undefined_names_in_annotations_are_reported_once() {
+    let source = "x: Undefined = 1";
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let outcome = Resolver::new(module.syntax()).run();
+    let inference = Inferrer::new(&module, &outcome.value).run();
+    let errors: Vec<_> = outcome
+        .errors
+        .into_iter()
+        .chain(inference.errors)
+        .map(|error| error.message)
+        .collect();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("name 'Undefined' is not defined"));
+}
+
+#[test]
+fn resolver_and_inferrer_never_report_the_same_error() {
+    let source = r#"
+from typing import Optional
+x: Undefined = 1
+y: "AlsoUndefined"
+z: Optional = None
+def f(a: int) -> "list[Missing]":
+    a = "a"
+    return missing(b)
+print(w)
+w = 1
+"#;
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let outcome = Resolver::new(module.syntax()).run();
+    let inference = Inferrer::new(&module, &outcome.value).run();
+    let resolver_errors: Vec<_> = outcome
+        .errors
+        .into_iter()
+        .map(|error| (error.range, error.message))
+        .collect();
+    assert!(!resolver_errors.is_empty());
+    let inferrer_errors: Vec<_> = inference
+        .errors
+        .into_iter()
+        .map(|error| (error.range, error.message))
+        .collect();
+    assert!(!inferrer_errors.is_empty());
+    for error in &inferrer_errors {
+        assert!(
+            !resolver_errors.contains(error),
+            "{error:?} is reported twice"
+        );
+    }
+}
+
+/// Infers the types in `source` and renders the module-level expression statements as their
+/// source text together with their types.
+fn expression_types(source: &str) -> Vec<String> {
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let resolution = Resolver::new(module.syntax()).run().value;
+    let inference = Inferrer::new(&module, &resolution).run().value;
+    module
+        .syntax()
+        .body
+        .iter()
+        .filter_map(|stmt| stmt.as_expr_stmt())
+        .map(|stmt| {
+            let node = stmt.value.node_index().load();
+            let ty = inference.expression_type(node).unwrap();
+            let text = &source[stmt.value.range()];
+            format!("{text}: {}", inference.types().display(ty))
+        })
+        .collect()
+}
+
+#[test]
+fn expressions_are_typed() {
+    let source = r#"
+import os.path
+from typing import Callable
+
+class A:
+    x: int = 0
+
+    def method(self, y: str) -> list[str]:
+        return [y]
+
+    @classmethod
+    def make(cls) -> "A":
+        return cls()
+
+def f(a: int, *args: str, b: bool = True, **kwargs: float) -> A:
+    return A()
+
+n = 1
+s = "s"
+t = (1, "a", None)
+first, *rest = 1, 2, 3
+xs = [n, 2.0]
+d = {"a": 1, **{"b": 2}}
+g: Callable[[int], str]
+
+n
+-1
+f"{n}"
+b"b"
+[1, "a"]
+{1, 2}
+{}
+d
+t
+t[-2]
+t[0:1]
+xs[0]
+d["a"]
+"abc"[0]
+A
+A()
+A().x
+A().method
+A.method
+A.make()
+f
+f(1)
+g(1)
+rest
+first
+n + 1
+n / 2
+s * 2
+xs + [s]
+not n
+n < 2
+n or s
+n if s else None
+lambda x, y=1: x
+[x * 2 for x in xs]
+{k: v for k, v in d.items()}
+{c for c in s}
+(x for x in xs)
+(y := s)
+list[int]()
+os
+len(s)
+"#;
+    assert_eq!(
+        expression_types(source),
+        &[
+            "n: int",
+            "-1: Literal[-1]",
+            "f\"{n}\": str",
+            "b\"b\": Literal[b'b']",
+            "[1, \"a\"]: list[int | str]",
+            "{1, 2}: set[int]",
+            "{}: dict[Any, Any]",
+            "d: dict[str, int]",
+            "t: tuple[int, str, None]",
+            "t[-2]: str",
+            "t[0:1]: tuple[int | str | None, ...]",
+            "xs[0]: int | float",
+            "d[\"a\"]: int",
+            "\"abc\"[0]: str",
+            "A: type[A]",
+            "A(): A",
+            "A().x: int",
+            "A().method: (y: str) -> list[str]",
+            "A.method: (self: A, y: str) -> list[str]",
+            "A.make(): Any",
+            "f: (a: int, *args: str, b: bool = ..., **kwargs: float) -> A",
+            "f(1): A",
+            "g(1): str",
+            "rest: list[int]",
+            "first: int",
+            "n + 1: int",
+            "n / 2: float",
+            "s * 2: str",
+            "xs + [s]: list[int | float | str]",
+            "not n: bool",
+            "n < 2: bool",
+            "n or s: int | str",
+            "n if s else None: int | None",
+            "lambda x, y=1: x: (x: Any, y: Any = ...) -> Any",
+            "[x * 2 for x in xs]: list[int | float]",
+            "{k: v for k, v in d.items()}: dict[Any, Any]",
+            "{c for c in s}: set[str]",
+            "(x for x in xs): Any",
+            "(y := s): str",
+            "list[int](): list[int]",
+            "os: Module(\"os\")",
+            "len(s): Any",
+        ]
+    );
+}
+
+#[test]
+fn all_expressions_are_typed() {
+    let source = r#"
+class A:
+    def f(self, x: int = 1, *args, **kwargs) -> None:
+        for i, (j, k) in enumerate(args):
+            print(f"{i!r:>{x}}", [j, *k], {**kwargs})
+        with open("f") as g:
+            del g
+        try:
+            pass
+        except (ValueError, TypeError) as e:
+            raise e from None
+        return None if x else print(lambda: x, [y for y in args if y], self.f()[1:2])
+"#;
+    /// Collects the expressions that are not annotations.
+    #[derive(Default)]
+    struct Collector<'a>(Vec<&'a ast::Expr>);
+
+    impl<'a> SourceOrderVisitor<'a> for Collector<'a> {
+        fn visit_expr(&mut self, expr: &'a ast::Expr) {
+            self.0.push(expr);
+            walk_expr(self, expr);
+        }
+
+        fn visit_annotation(&mut self, _: &'a ast::Expr) {}
+    }
+
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let resolution = Resolver::new(module.syntax()).run().value;
+    let inference = Inferrer::new(&module, &resolution).run().value;
+    let mut collector = Collector::default();
+    collector.visit_body(&module.syntax().body);
+    assert!(!collector.0.is_empty());
+    for expr in collector.0 {
+        assert!(
+            inference
+                .expression_type(expr.node_index().load())
+                .is_some(),
+            "no type for {:?}",
+            &source[expr.range()]
+        );
+    }
+}
+
+/// Infers the types in `source` and renders the errors together with the text they and their notes
+/// point at.
+fn inference_errors(source: &str) -> Vec<String> {
+    let module = IndexedModule::new(parser::parse_module(source).unwrap());
+    let resolution = Resolver::new(module.syntax()).run().value;
+    let outcome = Inferrer::new(&module, &resolution).run();
+    outcome
+        .errors
+        .into_iter()
+        .map(|error| {
+            let mut result = format!("{} at {:?}", error.message, &source[error.range]);
+            for note in error.notes {
+                write!(result, "; {} at {:?}", note.message, &source[note.range]).unwrap();
+            }
+            result
+        })
+        .collect()
+}
+
+#[test]
+fn assignments_are_checked_against_declared_types() {
+    let source = r#"
+from typing import Callable, Literal
+
+class A: ...
+
+a: int
+a = 1
+a = "one"
+b: float = 1
+b = True
+b = None
+c: int | None = None
+c = A()
+d: tuple[int, str]
+d = (1, "a")
+d = ("a", 1)
+e: list[float] = [1, 2.0]
+e = ["a"]
+f: Literal["x"] = "x"
+f = "y"
+g: int
+g, h = 1, "a"
+g, h = "a", 1
+i: list[int]
+first, *i = 1, 2, 3
+j: int = 0
+j += 1
+j /= 2
+k: type[A] = A
+k = int
+l: Callable[[], A] = A
+m: str = 1
+
+def func(n: int) -> None:
+    n = "n"
+"#;
+    assert_eq!(
+        inference_errors(source),
+        &[
+            "a value of type 'Literal['one']' is not assignable to 'a' of type 'int' at \
+             \"\\\"one\\\"\"; 'a' is declared here at \"a: int\"",
+            "a value of type 'None' is not assignable to 'b' of type 'float' at \"None\"; 'b' \
+             is declared here at \"b: float = 1\"",
+            "a value of type 'A' is not assignable to 'c' of type 'int | None' at \"A()\"; 'c' \
+             is declared here at \"c: int | None = None\"",
+            "a value of type 'tuple[Literal['a'], Literal[1]]' is not assignable to 'd' of type \
+             'tuple[int, str]' at \"(\\\"a\\\", 1)\"; 'd' is declared here at \"d: tuple[int, \
+             str]\"",
+            "a value of type 'list[str]' is not assignable to 'e' of type 'list[float]' at \
+             \"[\\\"a\\\"]\"; 'e' is declared here at \"e: list[float] = [1, 2.0]\"",
+            "a value of type 'Literal['y']' is not assignable to 'f' of type 'Literal['x']' at \
+             \"\\\"y\\\"\"; 'f' is declared here at \"f: Literal[\\\"x\\\"] = \\\"x\\\"\"",
+            "a value of type 'Literal['a']' is not assignable to 'g' of type 'int' at \
+             \"\\\"a\\\", 1\"; 'g' is declared here at \"g: int\"",
+            "a value of type 'float' is not assignable to 'j' of type 'int' at \"2\"; 'j' is \
+             declared here at \"j: int = 0\"",
+            "a value of type 'type[int]' is not assignable to 'k' of type 'type[A]' at \"int\"; \
+             'k' is declared here at \"k: type[A] = A\"",
+            "a value of type 'Literal[1]' is not assignable to 'm' of type 'str' at \"1\"",
+            "a value of type 'Literal['n']' is not assignable to 'n' of type 'int' at \
+             \"\\\"n\\\"\"; 'n' is declared here at \"n: int\"",
+        ]
+    );
+}
+
+#[test]
+fn subclasses_and_unknown_classes_are_assignable() {
+    let source = r#"
+from typing import Callable, Iterable, Mapping, Sequence, TypeVar
+from errors import AppError
+
+T = TypeVar("T")
+
+class A: ...
+class B(A): ...
+class C(B): ...
+class E(Sequence[int]): ...
+class F(AppError): ...
+
+a: A = C()
+b: Exception = ValueError()
+c: LookupError = KeyError()
+d: BaseException = KeyboardInterrupt()
+e: OSError = ConnectionResetError()
+f: int = True
+g: Sequence[int] = [1]
+h: Iterable[int] = range(3)
+i: Mapping[str, int] = {}
+j: Sequence[str] = "abc"
+k: Sequence[int] = E()
+l: type[A] = B
+
+def m(x: int, y: int = 0, *args: int) -> B:
+    return B()
+
+n: Callable[[int], A] = m
+o: Callable[[int, int, int], A] = m
+# Imported classes might be subclasses of any class not defined in the module.
+r: Exception = F()
+
+def p(x: T) -> T:
+    q: int = x
+    return x
+"#;
+    assert!(inference_errors(source).is_empty());
+}
+
+#[test]
+fn callables_match_keyword_parameters_by_name() {
+    use ParameterKind::*;
+
+    let module = IndexedModule::new(parser::parse_module("").unwrap());
+    let resolution = Resolver::new(module.syntax()).run().value;
+    let mut inferrer = Inferrer::new(&module, &resolution);
+    let mut callable = |parameters: &[(ParameterKind, &str)]| {
+        let parameters = parameters
+            .iter()
+            .map(|(kind, name)| parameter(*kind, Some(name), TypeTable::ANY, false))
+            .collect();
+        inferrer.types().intern(Type::Callable {
+            parameters: Some(parameters),
+            returns: TypeTable::NONE,
+        })
+    };
+    let target = callable(&[(PositionalOrKeyword, "x"), (KeywordOnly, "key")]);
+    let same = callable(&[(PositionalOrKeyword, "x"), (KeywordOnly, "key")]);
+    let positional_key = callable(&[(PositionalOrKeyword, "x"), (PositionalOrKeyword, "key")]);
+    let kwargs = callable(&[(PositionalOrKeyword, "x"), (VarKeyword, "kwargs")]);
+    let renamed_key = callable(&[(PositionalOrKeyword, "x"), (KeywordOnly, "other")]);
+    let renamed_x = callable(&[(PositionalOrKeyword, "y"), (KeywordOnly, "key")]);
+    let positional_only = callable(&[(PositionalOnly, "x"), (KeywordOnly, "key")]);
+    let swapped = callable(&[(PositionalOrKeyword, "key"), (KeywordOnly, "x")]);
+    assert!(inferrer.is_assignable(same, target));
+    assert!(inferrer.is_assignable(positional_key, target));
+    assert!(inferrer.is_assignable(kwargs, target));
+    assert!(!inferrer.is_assignable(renamed_key, target));
+    assert!(!inferrer.is_assignable(renamed_x, target));
+    assert!(!inferrer.is_assignable(positional_only, target));
+    assert!(!inferrer.is_assignable(swapped, target));
+    assert!(!inferrer.is_assignable(target, positional_key));
+}
+
+#[test]
+fn unrelated_classes_are_not_assignable() {
+    let source = r#"
+from typing import Callable, Sequence
+
+class A: ...
+class B(A): ...
+class C(object): ...
+class D(Sequence[int]): ...
+
+def f(x: int, y: int) -> B:
+    return B()
+
+a: B = A()
+b: A = C()
+c: ValueError = Exception()
+d: KeyError = IndexError()
+e: int = A()
+g: Callable[[int], A] = f
+h: Callable[[str, int], A] = f
+i: A = D()
+"#;
+    assert_eq!(
+        inference_errors(source),
+        &[
+            "a value of type 'A' is not assignable to 'a' of type 'B' at \"A()\"",
+            "a value of type 'C' is not assignable to 'b' of type 'A' at \"C()\"",
+            "a value of type 'Exception' is not assignable to 'c' of type 'ValueError' at \
+             \"Exception()\"",
+            "a value of type 'IndexError' is not assignable to 'd' of type 'KeyError' at \
+             \"IndexError()\"",
+            "a value of type 'A' is not assignable to 'e' of type 'int' at \"A()\"",
+            "a value of type '(x: int, y: int) -> B' is not assignable to 'g' of type \
+             'Callable[[int], A]' at \"f\"",
+            "a value of type '(x: int, y: int) -> B' is not assignable to 'h' of type \
+             'Callable[[str, int], A]' at \"f\"",
+            "a value of type 'D' is not assignable to 'i' of type 'A' at \"D()\"",
+        ]
+    );
+}